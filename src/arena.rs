@@ -0,0 +1,346 @@
+//! 2つのプレイヤーの対戦とElo差の推定
+//! 
+
+use crate::board::Board;
+use crate::game::Game;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use std::collections::HashSet;
+use std::fmt;
+
+// 95%信頼区間の幅(標準正規分布)
+const Z_95: f64 = 1.959_964;
+
+/// 初期配置から`plies`手進めた局面を、対称変換で同じになるものを除いて全て返します。
+/// 手順は打てる場所の並び順で辿るため、返す順番は常に同じです。
+/// 
+pub fn openings(x_size: usize, y_size: usize, plies: usize) -> Vec<(Board, PieceType)> {
+    let mut openings = Vec::new();
+    let mut seen = HashSet::new();
+    collect_openings(Board::new(x_size, y_size), PieceType::Black, plies, &mut seen, &mut openings);
+    openings
+}
+
+fn collect_openings(board: Board, turn: PieceType, plies: usize, seen: &mut HashSet<(u64, usize)>, openings: &mut Vec<(Board, PieceType)>) {
+    if board.is_end() || !seen.insert((board.canonical_key(turn).0, plies)) {
+        return;
+    }
+    if plies == 0 {
+        openings.push((board, turn));
+        return;
+    }
+    if board.is_skip(turn) {
+        collect_openings(board, turn.change(), plies, seen, openings);
+        return;
+    }
+
+    for point in board.legal_moves(turn) {
+        collect_openings(board.clone().put_piece(turn, point), turn.change(), plies - 1, seen, openings);
+    }
+}
+
+/// `black`と`white`で`board`から終局まで対局し、黒から見た最終石差を返します。
+/// 打てない手を選んだ側はその時点で負けになり、石差は盤面のマスの数になります。
+/// 
+pub fn play_game(black: &mut dyn Player, white: &mut dyn Player, board: &Board, turn: PieceType) -> i32 {
+    let mut game = Game::from_board(board.clone(), turn);
+    let squares = (board.x_size() * board.y_size()) as i32;
+
+    while !game.is_end() {
+        let turn = game.turn();
+        if game.must_pass() {
+            // must_passがtrueならパスは必ず成功する
+            let _ = game.pass();
+            continue;
+        }
+
+        let choice = if turn == PieceType::Black {
+            black.choose_move(game.board(), turn)
+        } else {
+            white.choose_move(game.board(), turn)
+        };
+        let legal = choice.map(|point| game.play(point));
+        if !matches!(legal, Some(Ok(()))) {
+            return if turn == PieceType::Black { -squares } else { squares };
+        }
+    }
+
+    game.board().final_score(PieceType::Black)
+}
+
+/// # Sprt 構造体
+/// 
+/// 逐次確率比検定(SPRT)の設定
+/// 
+/// Elo差が`elo0`である仮説H0と`elo1`である仮説H1を比べ、
+/// 対数尤度比が上限を超えればH1、下限を下回ればH0を採択して対戦を打ち切ります。
+/// 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// 第一種の誤りの確率
+    pub alpha: f64,
+    /// 第二種の誤りの確率
+    pub beta: f64,
+}
+
+/// SPRTの判定
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SprtDecision {
+    /// H0を採択(強くなっていない)
+    AcceptH0,
+    /// H1を採択(強くなった)
+    AcceptH1,
+    /// まだ判定できない
+    Continue,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Sprt {
+        Sprt { elo0, elo1, alpha, beta }
+    }
+
+    /// 勝ち・引き分け・負けの数から対数尤度比を返します。
+    /// 
+    /// 1局ごとの得点の平均と分散を使った正規近似で計算します。
+    /// 
+    pub fn llr(&self, result: &MatchResult) -> f64 {
+        let games = result.games() as f64;
+        if result.wins == 0 || result.losses == 0 {
+            // 分散が偏りすぎて近似が使えないため、1局分の勝ちと負けを足して計算する
+            let adjusted = MatchResult { wins: result.wins + 1, losses: result.losses + 1, ..*result };
+            return self.llr(&adjusted) * games / adjusted.games() as f64;
+        }
+
+        let score = result.score();
+        let variance = result.variance() / games;
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    /// 対戦結果から判定します。
+    /// 
+    pub fn decide(&self, result: &MatchResult) -> SprtDecision {
+        let llr = self.llr(result);
+        if llr >= ((1.0 - self.beta) / self.alpha).ln() {
+            SprtDecision::AcceptH1
+        } else if llr <= (self.beta / (1.0 - self.alpha)).ln() {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+/// # MatchResult 構造体
+/// 
+/// 1つ目のプレイヤーから見た対戦結果
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MatchResult {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// 石差の合計
+    pub disc_difference: i64,
+}
+
+impl MatchResult {
+    /// 1局分の石差を加えます。
+    /// 
+    pub fn add(&mut self, disc_difference: i32) {
+        match disc_difference.signum() {
+            1 => self.wins += 1,
+            0 => self.draws += 1,
+            _ => self.losses += 1,
+        }
+        self.disc_difference += disc_difference as i64;
+    }
+
+    /// 対局数を返します。
+    /// 
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// 勝ちを1、引き分けを0.5とした1局あたりの得点を返します。
+    /// 
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// 1局あたりの平均石差を返します。
+    /// 
+    pub fn average_disc_difference(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        self.disc_difference as f64 / self.games() as f64
+    }
+
+    /// 得点から推定したElo差を返します。全勝や全敗の場合は無限大になります。
+    /// 
+    pub fn elo(&self) -> f64 {
+        elo(self.score())
+    }
+
+    /// Elo差の95%信頼区間を(下限, 上限)で返します。
+    /// 
+    pub fn elo_interval(&self) -> (f64, f64) {
+        if self.games() == 0 {
+            return (f64::NEG_INFINITY, f64::INFINITY);
+        }
+        let margin = Z_95 * (self.variance() / self.games() as f64).sqrt();
+        let score = self.score();
+        (elo((score - margin).max(0.0)), elo((score + margin).min(1.0)))
+    }
+
+    // 1局ごとの得点の分散
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        let games = self.games() as f64;
+        (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2)) / games
+    }
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lower, upper) = self.elo_interval();
+        write!(
+            f,
+            "{}勝 {}分 {}敗 平均石差 {:+.2} Elo {:+.1} (95%: {:+.1} 〜 {:+.1})",
+            self.wins, self.draws, self.losses, self.average_disc_difference(), self.elo(), lower, upper
+        )
+    }
+}
+
+/// # Arena 構造体
+/// 
+/// 2つのプレイヤーを同じ開始局面から先後を入れ替えて対戦させる構造体
+/// 
+pub struct Arena {
+    openings: Vec<(Board, PieceType)>,
+    sprt: Option<Sprt>,
+}
+
+impl Arena {
+    /// `openings`の局面から対戦させます。局面ごとに先後を入れ替えて2局ずつ対局します。
+    /// 
+    pub fn new(openings: Vec<(Board, PieceType)>) -> Arena {
+        Arena {
+            openings,
+            sprt: None,
+        }
+    }
+
+    /// 2局ごとにSPRTで判定し、判定できた時点で対戦を打ち切ります。
+    /// 
+    pub fn set_sprt(&mut self, sprt: Sprt) {
+        self.sprt = Some(sprt);
+    }
+
+    /// `first`と`second`を対戦させ、`first`から見た結果とSPRTの判定を返します。
+    /// SPRTを設定していない場合の判定はNoneです。
+    /// 
+    pub fn play(&self, first: &mut dyn Player, second: &mut dyn Player) -> (MatchResult, Option<SprtDecision>) {
+        let mut result = MatchResult::default();
+        let mut decision = self.sprt.map(|_| SprtDecision::Continue);
+
+        for (board, turn) in &self.openings {
+            // 開始局面の手番を`first`、`second`の順に持たせる。石差は`first`から見た値にする
+            if *turn == PieceType::Black {
+                result.add(play_game(first, second, board, *turn));
+                result.add(-play_game(second, first, board, *turn));
+            } else {
+                result.add(-play_game(second, first, board, *turn));
+                result.add(play_game(first, second, board, *turn));
+            }
+
+            if let Some(sprt) = &self.sprt {
+                decision = Some(sprt.decide(&result));
+                if decision != Some(SprtDecision::Continue) {
+                    break;
+                }
+            }
+        }
+
+        (result, decision)
+    }
+}
+
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10_f64.powf(-elo / 400.0))
+}
+
+fn elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greedy_player::GreedyPlayer;
+    use crate::random_player::RandomPlayer;
+
+    #[test]
+    fn test_openings() {
+        // 2手目までの局面は縦取り・斜め取り・並び取りの3通り
+        assert_eq!(1, openings(8, 8, 1).len());
+        assert_eq!(3, openings(8, 8, 2).len());
+        assert!(openings(8, 8, 2).iter().all(|(board, turn)| board.empty_count() == 58 && *turn == PieceType::Black));
+    }
+
+    #[test]
+    fn test_play() {
+        let arena = Arena::new(openings(8, 8, 2));
+
+        // 同じプレイヤー同士は先後を入れ替えると結果が反転する
+        let (result, decision) = arena.play(&mut GreedyPlayer::new(), &mut GreedyPlayer::new());
+        assert_eq!(6, result.games());
+        assert_eq!(result.wins, result.losses);
+        assert_eq!(0, result.disc_difference);
+        assert_eq!(None, decision);
+
+        let (result, _) = arena.play(&mut GreedyPlayer::new(), &mut RandomPlayer::new(1));
+        assert_eq!(6, result.games());
+    }
+
+    #[test]
+    fn test_elo() {
+        let result = MatchResult { wins: 60, draws: 20, losses: 20, disc_difference: 500 };
+        assert!((result.elo() - 147.19).abs() < 0.01);
+        assert_eq!(5.0, result.average_disc_difference());
+
+        let (lower, upper) = result.elo_interval();
+        assert!(lower < result.elo() && result.elo() < upper);
+        assert!(lower > 0.0);
+
+        let even = MatchResult { wins: 10, draws: 0, losses: 10, disc_difference: 0 };
+        assert_eq!(0.0, even.elo());
+        assert!(result.to_string().starts_with("60勝 20分 20敗 平均石差 +5.00 Elo +147.2"));
+    }
+
+    #[test]
+    fn test_sprt() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        assert_eq!(SprtDecision::AcceptH1, sprt.decide(&MatchResult { wins: 600, draws: 100, losses: 300, disc_difference: 0 }));
+        assert_eq!(SprtDecision::AcceptH0, sprt.decide(&MatchResult { wins: 300, draws: 100, losses: 600, disc_difference: 0 }));
+        assert_eq!(SprtDecision::Continue, sprt.decide(&MatchResult { wins: 5, draws: 0, losses: 5, disc_difference: 0 }));
+
+        // 判定できた時点で打ち切る
+        let mut arena = Arena::new(openings(8, 8, 4));
+        arena.set_sprt(Sprt::new(-400.0, 400.0, 0.2, 0.2));
+        let (result, decision) = arena.play(&mut GreedyPlayer::new(), &mut RandomPlayer::new(3));
+        assert_ne!(Some(SprtDecision::Continue), decision);
+        assert!((result.games() as usize) < openings(8, 8, 4).len() * 2);
+    }
+}
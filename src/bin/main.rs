@@ -1,31 +1,34 @@
 use std::io::{self, Write};
 
 extern crate rust_reversi;
-use rust_reversi::board::Board;
-use rust_reversi::piece_type::PieceType;
+use rust_reversi::game::Game;
 use rust_reversi::point::Point;
 
 fn main() {
-    let mut board = Board::new(8, 8);
-
-    let mut piece_type = PieceType::Black;
+    let mut game = Game::new(8, 8);
 
     // 画面表示
-    board.print();
+    game.board().print();
 
     loop {
-        if board.is_end() {
+        if game.is_end() {
             break;
         }
 
-        if board.is_skip(piece_type) {
+        let piece_type = game.turn();
+
+        if game.must_pass() {
             println!("{} の打てる場所がありません", piece_type);
-            piece_type = piece_type.change();
+            if let Err(err) = game.pass() {
+                println!("{}", err);
+                break;
+            }
+            continue;
         }
 
         println!("{} の番です", piece_type);
 
-        let point = match input_xy() {
+        let point = match input_point() {
             Ok(point) => point,
             Err(err) => {
                 println!("{}", err);
@@ -33,34 +36,29 @@ fn main() {
             },
         };
 
-        if board.can_put_piece(piece_type, &point) {
-            board = board.put_piece(piece_type, point);
-            piece_type = piece_type.change();
-
-            println!("{} に置きました", point);
-            board.print();
-        } else {
-            println!("{} には置けません", point);
+        match game.play(point) {
+            Ok(()) => {
+                println!("{} に置きました", point);
+                game.board().print();
+            },
+            Err(err) => println!("{}", err),
         }
     }
 
-    println!("ゲーム終了");
+    match game.end_reason() {
+        Some(end_reason) => println!("ゲーム終了 ({})", end_reason),
+        None => println!("ゲーム終了"),
+    }
 
-    if let Some(piece_type) = board.get_winner() {
+    if let Some(piece_type) = game.get_winner() {
         println!("{} の勝利!", piece_type)
     } else {
         println!("同点")
     }
 }
 
-fn input_xy() -> Result<Point, String> {
-    let x = input_axes("x")?;
-    let y = input_axes("y")?;
-    Ok(Point::new(x, y))
-}
-
-fn input_axes(axes: &str) -> Result<usize, String> {
-    print!("{}座標を入力してください : ", axes);
+fn input_point() -> Result<Point, String> {
+    print!("座標を入力してください (例: d3) : ");
     io::stdout().flush().unwrap();
 
     let mut val = String::new();
@@ -68,12 +66,5 @@ fn input_axes(axes: &str) -> Result<usize, String> {
         Ok(_) => (),
         Err(error) => println!("error: {}", error),
     }
-    let val: usize = match val.trim().parse() {
-        Ok(v) => v,
-        Err(_) => {
-            return Err(format!("{}の値が不正です", axes));
-        }
-    };
-
-    Ok(val)
+    val.parse::<Point>().map_err(|err| err.to_string())
 }
\ No newline at end of file
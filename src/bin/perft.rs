@@ -0,0 +1,84 @@
+use std::env;
+use std::process;
+use std::time::Instant;
+
+extern crate rust_reversi;
+use rust_reversi::board::Board;
+use rust_reversi::perft;
+use rust_reversi::piece_type::PieceType;
+
+const USAGE: &str = "使い方: perft [--depth N] [--size N] [--position 局面] [--divide]";
+
+struct Options {
+    depth: usize,
+    size: usize,
+    position: Option<String>,
+    divide: bool,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let (board, turn) = match &options.position {
+        Some(position) => match Board::from_position_str(position, options.size, options.size) {
+            Ok(position) => position,
+            Err(err) => {
+                eprintln!("局面が不正です : {}", err);
+                process::exit(2);
+            }
+        },
+        None => (Board::new(options.size, options.size), PieceType::Black),
+    };
+
+    let start = Instant::now();
+    let nodes = if options.divide {
+        let counts = perft::divide(&board, turn, options.depth);
+        for (point, nodes) in &counts {
+            match point {
+                Some(point) => println!("{} {}", point, nodes),
+                None => println!("pass {}", nodes),
+            }
+        }
+        counts.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        perft::perft(&board, turn, options.depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("depth {} : {} nodes", options.depth, nodes);
+    eprintln!("{:.3}秒 ({:.0} nodes/秒)", elapsed.as_secs_f64(), nodes as f64 / elapsed.as_secs_f64().max(1e-9));
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        depth: 6,
+        size: 8,
+        position: None,
+        divide: false,
+    };
+
+    while let Some(arg) = args.next() {
+        if arg == "--divide" {
+            options.divide = true;
+            continue;
+        }
+
+        let value = args.next().ok_or(format!("{} の値がありません", arg))?;
+        let number = || value.parse::<usize>().map_err(|_| format!("{} の値が不正です : {}", arg, value));
+        match arg.as_str() {
+            "--depth" => options.depth = number()?,
+            "--size" => options.size = number()?,
+            "--position" => options.position = Some(value),
+            _ => return Err(format!("知らないオプションです : {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,170 @@
+use std::cell::RefCell;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
+use std::rc::Rc;
+
+extern crate rust_reversi;
+use rust_reversi::board::Board;
+use rust_reversi::greedy_player::GreedyPlayer;
+use rust_reversi::mcts::{Budget, MctsPlayer};
+use rust_reversi::piece_type::PieceType;
+use rust_reversi::player::Player;
+use rust_reversi::point::Point;
+use rust_reversi::random_player::RandomPlayer;
+use rust_reversi::score_type::ScoreType;
+use rust_reversi::search::{Search, SearchResult};
+use rust_reversi::self_play::{self, ScoreSource, SelfPlay};
+
+const USAGE: &str = "使い方: selfplay [--games N] [--random N] [--first 種類] [--second 種類] [--depth N] [--endgame N] [--playouts N] [--seed N] [--size N] [--format csv|binary] [--output ファイル]\n\
+                     種類: search(既定)・mcts・greedy・random。評価値は常に--depthと--endgameの探索で求め、searchの手番では手を選んだ探索の結果を使います";
+
+struct Options {
+    self_play: SelfPlay,
+    first: String,
+    second: String,
+    depth: usize,
+    playouts: u32,
+    endgame: usize,
+    binary: bool,
+    output: Option<String>,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let last = LastSearch::default();
+    let mut first = create_player(&options.first, &options, 1, &last);
+    let mut second = create_player(&options.second, &options, 2, &last);
+    let mut scorer = Scorer { search: create_search(&options), last };
+
+    let self_play = options.self_play;
+    let mut records = Vec::new();
+    for index in 0..self_play.games {
+        records.extend(self_play.play_game(index, first.as_mut(), second.as_mut(), &mut scorer));
+        eprintln!("{}/{} 局終了 ({} 局面)", index + 1, self_play.games, records.len());
+    }
+
+    let result = match &options.output {
+        Some(path) => File::create(path).and_then(|file| write(&options, &records, BufWriter::new(file))),
+        None => write(&options, &records, BufWriter::new(io::stdout().lock())),
+    };
+    if let Err(err) = result {
+        eprintln!("書き出せませんでした : {}", err);
+        process::exit(1);
+    }
+}
+
+fn create_search(options: &Options) -> Search {
+    let mut search = Search::new(options.depth);
+    search.set_endgame_empties(options.endgame);
+    search
+}
+
+// 種類は`parse_args`で確かめてある
+fn create_player(kind: &str, options: &Options, index: u64, last: &LastSearch) -> Box<dyn Player> {
+    let seed = options.self_play.seed.wrapping_add(index);
+    match kind {
+        "mcts" => Box::new(MctsPlayer::new(Budget::Iterations(options.playouts), seed)),
+        "greedy" => Box::new(GreedyPlayer::new()),
+        "random" => Box::new(RandomPlayer::new(seed)),
+        _ => Box::new(SharedSearch { search: create_search(options), last: Rc::clone(last) }),
+    }
+}
+
+// 探索のプレイヤーが最後に読んだ局面と結果。評価値と設定が同じなので、同じ局面を2度探索しないよう使い回す
+type LastSearch = Rc<RefCell<Option<(Board, PieceType, SearchResult)>>>;
+
+struct SharedSearch {
+    search: Search,
+    last: LastSearch,
+}
+
+impl Player for SharedSearch {
+    fn choose_move(&mut self, board: &Board, piece_type: PieceType) -> Option<Point> {
+        let result = self.search.search(board, piece_type);
+        let best_move = result.best_move;
+        *self.last.borrow_mut() = Some((board.clone(), piece_type, result));
+        best_move
+    }
+}
+
+struct Scorer {
+    search: Search,
+    last: LastSearch,
+}
+
+impl ScoreSource for Scorer {
+    fn score(&mut self, board: &Board, piece_type: PieceType) -> (i32, ScoreType) {
+        match self.last.borrow_mut().take() {
+            Some((last_board, last_turn, result)) if last_turn == piece_type && last_board == *board => self_play::record_score(&result),
+            _ => self.search.score(board, piece_type),
+        }
+    }
+}
+
+fn write<W: Write>(options: &Options, records: &[self_play::Record], mut writer: W) -> io::Result<()> {
+    if options.binary {
+        self_play::write_binary(records, options.self_play.x_size, options.self_play.y_size, &mut writer)?;
+    } else {
+        self_play::write_csv(records, &mut writer)?;
+    }
+    writer.flush()
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        self_play: SelfPlay::new(1, 8, 0),
+        first: "search".to_string(),
+        second: "search".to_string(),
+        depth: 4,
+        playouts: 1000,
+        endgame: 10,
+        binary: false,
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{} の値がありません", arg))?;
+        let number = || value.parse::<u64>().map_err(|_| format!("{} の値が不正です : {}", arg, value));
+        match arg.as_str() {
+            "--games" => options.self_play.games = number()? as usize,
+            "--random" => options.self_play.random_moves = number()? as usize,
+            "--first" | "--second" => {
+                if !["search", "mcts", "greedy", "random"].contains(&value.as_str()) {
+                    return Err(format!("知らないプレイヤーです : {}", value));
+                }
+                if arg == "--first" {
+                    options.first = value;
+                } else {
+                    options.second = value;
+                }
+            }
+            "--depth" => options.depth = number()? as usize,
+            "--playouts" => options.playouts = number()? as u32,
+            "--endgame" => options.endgame = number()? as usize,
+            "--seed" => options.self_play.seed = number()?,
+            "--size" => {
+                options.self_play.x_size = number()? as usize;
+                options.self_play.y_size = options.self_play.x_size;
+            }
+            "--format" => match value.as_str() {
+                "csv" => options.binary = false,
+                "binary" => options.binary = true,
+                _ => return Err(format!("知らない形式です : {}", value)),
+            },
+            "--output" => options.output = Some(value),
+            _ => return Err(format!("知らないオプションです : {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,286 @@
+use crate::point::Point;
+use crate::piece_type::PieceType;
+use crate::tile_type::TileType;
+
+/// 8x8盤面の一辺の長さ
+pub const SIZE: usize = 8;
+
+// 横方向のシフトで反対側の列に回り込まないためのマスク
+const NOT_LEFT: u64 = 0xfefe_fefe_fefe_fefe;
+const NOT_RIGHT: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+// 8方向 (シフト量, マスク)。正のシフトは左シフト、負のシフトは右シフトを表す。
+const DIRECTIONS: [(i32, u64); 8] = [
+    (1, NOT_LEFT), (-1, NOT_RIGHT),
+    (8, u64::MAX), (-8, u64::MAX),
+    (9, NOT_LEFT), (7, NOT_RIGHT),
+    (-7, NOT_LEFT), (-9, NOT_RIGHT),
+];
+
+/// # Bitboard 構造体
+/// 
+/// 8x8の盤面を黒と白の2つの`u64`で表した構造体
+/// 
+/// `Point`の(x, y)は `(y - 1) * 8 + (x - 1)` のビットに対応します。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Bitboard {
+    pub black: u64,
+    pub white: u64,
+}
+
+impl Bitboard {
+    /// 初期配置の盤面を生成します。
+    /// 
+    pub fn new() -> Bitboard {
+        let mut bitboard = Bitboard {
+            black: 0,
+            white: 0,
+        };
+        bitboard.set(&Point::new(4, 4), TileType::Piece(PieceType::White));
+        bitboard.set(&Point::new(5, 4), TileType::Piece(PieceType::Black));
+        bitboard.set(&Point::new(4, 5), TileType::Piece(PieceType::Black));
+        bitboard.set(&Point::new(5, 5), TileType::Piece(PieceType::White));
+        bitboard
+    }
+
+    /// `Point`に対応するビット位置を返します。盤内でない場合はNoneが返却されます。
+    /// 
+    pub fn index(point: &Point) -> Option<u32> {
+        if (1..=SIZE).contains(&point.x) && (1..=SIZE).contains(&point.y) {
+            Some(((point.y - 1) * SIZE + (point.x - 1)) as u32)
+        } else {
+            None
+        }
+    }
+
+    /// ビット位置に対応する`Point`を返します。
+    /// 
+    pub fn point(index: u32) -> Point {
+        let index = index as usize;
+        Point::new(index % SIZE + 1, index / SIZE + 1)
+    }
+
+    /// ビットが立っている位置を`Point`として列挙します。
+    /// 
+    pub fn points(bits: u64) -> impl Iterator<Item = Point> {
+        Bitboard::indexes(bits).map(Bitboard::point)
+    }
+
+    /// ビットが立っている位置を下位ビットから順に列挙します。
+    /// 
+    pub fn indexes(mut bits: u64) -> impl Iterator<Item = u32> {
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                None
+            } else {
+                let index = bits.trailing_zeros();
+                bits &= bits - 1;
+                Some(index)
+            }
+        })
+    }
+
+    /// 指定した位置のタイルを返します。
+    /// 盤外(番兵)の位置は`TileType::None`、範囲外の位置はNoneが返却されます。
+    /// 
+    pub fn get(&self, point: &Point) -> Option<&'static TileType> {
+        match Bitboard::index(point) {
+            Some(index) => {
+                let bit = 1_u64 << index;
+                if self.black & bit != 0 {
+                    Some(&TileType::Piece(PieceType::Black))
+                } else if self.white & bit != 0 {
+                    Some(&TileType::Piece(PieceType::White))
+                } else {
+                    Some(&TileType::Square)
+                }
+            }
+            None if point.x <= SIZE + 1 && point.y <= SIZE + 1 => Some(&TileType::None),
+            None => None,
+        }
+    }
+
+    /// 指定した位置のタイルを変更します。
+    /// 盤外の位置は常に`TileType::None`のため変更されません。
+    /// 
+    pub fn set(&mut self, point: &Point, tile_type: TileType) {
+        if let Some(index) = Bitboard::index(point) {
+            let bit = 1_u64 << index;
+            self.black &= !bit;
+            self.white &= !bit;
+            match tile_type {
+                TileType::Piece(PieceType::Black) => self.black |= bit,
+                TileType::Piece(PieceType::White) => self.white |= bit,
+                TileType::Square | TileType::None => (),
+            }
+        }
+    }
+
+    /// 指定した駒の配置を返します。
+    /// 
+    pub fn pieces(&self, piece_type: PieceType) -> u64 {
+        match piece_type {
+            PieceType::Black => self.black,
+            PieceType::White => self.white,
+        }
+    }
+
+    /// 空いているマスを返します。
+    /// 
+    pub fn empties(&self) -> u64 {
+        !(self.black | self.white)
+    }
+
+    /// `bits`の各位置に隣接する位置を返します。
+    /// 
+    pub fn neighbors(bits: u64) -> u64 {
+        DIRECTIONS.iter().fold(0, |acc, &(shift_size, mask)| acc | shift(bits, shift_size, mask))
+    }
+
+    /// 駒を置ける位置を返します。
+    /// 
+    pub fn legal_moves(&self, piece_type: PieceType) -> u64 {
+        legal_moves(self.pieces(piece_type), self.pieces(piece_type.change()))
+    }
+
+    /// `index`の位置に駒を置いた時に返る駒を返します。
+    /// 空いていない位置の場合は0が返却されます。
+    /// 
+    pub fn flips(&self, piece_type: PieceType, index: u32) -> u64 {
+        let bit = 1_u64 << index;
+        if self.empties() & bit == 0 {
+            return 0;
+        }
+
+        flips(self.pieces(piece_type), self.pieces(piece_type.change()), index)
+    }
+
+    /// `index`の位置に駒を置き、`flips`の駒を返します。
+    /// 
+    pub fn apply(&mut self, piece_type: PieceType, index: u32, flips: u64) {
+        let bit = 1_u64 << index;
+        match piece_type {
+            PieceType::Black => {
+                self.black |= bit | flips;
+                self.white &= !flips;
+            }
+            PieceType::White => {
+                self.white |= bit | flips;
+                self.black &= !flips;
+            }
+        }
+    }
+
+    /// `apply`で置いた駒を取り除き、`flips`の駒を元に戻します。
+    /// 
+    pub fn restore(&mut self, piece_type: PieceType, index: u32, flips: u64) {
+        let bit = 1_u64 << index;
+        match piece_type {
+            PieceType::Black => {
+                self.black &= !(bit | flips);
+                self.white |= flips;
+            }
+            PieceType::White => {
+                self.white &= !(bit | flips);
+                self.black |= flips;
+            }
+        }
+    }
+}
+
+/// 手番の駒`player`と相手の駒`opponent`から、手番が駒を置ける位置を返します。
+/// 
+pub fn legal_moves(player: u64, opponent: u64) -> u64 {
+    let empties = !(player | opponent);
+
+    let mut moves = 0;
+    for (shift_size, mask) in DIRECTIONS {
+        // 自分の駒から相手の駒が続く範囲を伸ばし、その先が空いていれば置ける
+        let mut line = shift(player, shift_size, mask) & opponent;
+        for _ in 0..5 {
+            line |= shift(line, shift_size, mask) & opponent;
+        }
+        moves |= shift(line, shift_size, mask) & empties;
+    }
+
+    moves
+}
+
+/// 手番の駒`player`と相手の駒`opponent`から、空いている`index`の位置に置いた時に返る駒を返します。
+/// 
+pub fn flips(player: u64, opponent: u64, index: u32) -> u64 {
+    let bit = 1_u64 << index;
+
+    let mut flips = 0;
+    for (shift_size, mask) in DIRECTIONS {
+        let mut line = 0;
+        let mut cursor = shift(bit, shift_size, mask);
+        while cursor & opponent != 0 {
+            line |= cursor;
+            cursor = shift(cursor, shift_size, mask);
+        }
+        // 相手の駒の先に自分の駒があれば挟める
+        if cursor & player != 0 {
+            flips |= line;
+        }
+    }
+
+    flips
+}
+
+fn shift(bits: u64, shift_size: i32, mask: u64) -> u64 {
+    if shift_size > 0 {
+        (bits << shift_size) & mask
+    } else {
+        (bits >> -shift_size) & mask
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legal_moves() {
+        let bitboard = Bitboard::new();
+
+        // 黒の初手は (4,3) (3,4) (6,5) (5,6)
+        let moves = [(4, 3), (3, 4), (6, 5), (5, 6)]
+            .iter()
+            .map(|&(x, y)| 1_u64 << Bitboard::index(&Point::new(x, y)).unwrap())
+            .fold(0, |acc, bit| acc | bit);
+        assert_eq!(moves, bitboard.legal_moves(PieceType::Black));
+    }
+
+    #[test]
+    fn test_flips() {
+        let mut bitboard = Bitboard::new();
+        let index = Bitboard::index(&Point::new(4, 3)).unwrap();
+
+        let flips = bitboard.flips(PieceType::Black, index);
+        assert_eq!(1_u64 << Bitboard::index(&Point::new(4, 4)).unwrap(), flips);
+
+        bitboard.apply(PieceType::Black, index, flips);
+        assert_eq!(4, bitboard.black.count_ones());
+        assert_eq!(1, bitboard.white.count_ones());
+
+        // 置いた場所にはもう置けない
+        assert_eq!(0, bitboard.flips(PieceType::White, index));
+    }
+
+    #[test]
+    fn test_no_wrap_around() {
+        let mut bitboard = Bitboard {
+            black: 0,
+            white: 0,
+        };
+
+        // 右端の白と次の行の左端の黒は挟めない
+        bitboard.set(&Point::new(8, 1), TileType::Piece(PieceType::White));
+        bitboard.set(&Point::new(1, 2), TileType::Piece(PieceType::Black));
+        assert_eq!(0, bitboard.legal_moves(PieceType::Black));
+    }
+}
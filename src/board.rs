@@ -1,28 +1,199 @@
+use crate::bitboard::Bitboard;
+use crate::end_reason::EndReason;
+use crate::move_error::MoveError;
+use crate::move_undo::{Flips, MoveUndo};
 use crate::point::Point;
 use crate::tile_type::TileType;
 use crate::piece_type::PieceType;
+use crate::symmetry::Symmetry;
 use crate::tile::Tile;
+use crate::zobrist;
 use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
 
 /// # Board 構造体
 /// 
 /// オセロの盤面を表している構造体
 /// 
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Board {
     tiles: Tile,
     x_size: usize,
     y_size: usize,
+    // `Symmetry::ALL`の順に対称変換した盤面のハッシュ値。先頭がこの盤面の値
+    hashes: [u64; 8],
 }
 
 impl Board {
     /// 新しく盤面を生成します。`x_size`と`y_size`は通常8を指定します。
     /// 
     pub fn new(x_size: usize, y_size: usize) -> Board {
-        Board {
+        let mut board = Board {
             tiles: Tile::new(x_size, y_size),
             x_size,
             y_size,
+            hashes: [0; 8],
+        };
+        board.hashes = board.compute_hashes();
+        board
+    }
+
+    /// 盤面の大きさに関わらずHashMapで管理する盤面を生成します。ビットボードとの比較に使います。
+    /// 
+    #[cfg(test)]
+    pub(crate) fn new_general(x_size: usize, y_size: usize) -> Board {
+        let mut board = Board {
+            tiles: Tile::new_general(x_size, y_size),
+            x_size,
+            y_size,
+            hashes: [0; 8],
+        };
+        board.hashes = board.compute_hashes();
+        board
+    }
+
+    /// 盤面のZobristハッシュ値を返します。駒を置く度に差分で更新されます。
+    /// 
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hashes[0]
+    }
+
+    /// 盤面と手番を合わせたハッシュ値を返します。置換表の検索に使います。
+    /// 
+    pub fn position_key(&self, turn: PieceType) -> u64 {
+        self.hashes[0] ^ zobrist::side_key(turn)
+    }
+
+    /// 対称変換で同じになる盤面の中で最小のハッシュ値と、その盤面にする対称変換を返します。
+    /// 回転や反転をした同じ局面を1つにまとめて検索する場合に使います。
+    /// 対称変換した盤面のハッシュ値も駒を置く度に差分で更新しているため、盤面を走査せずに求められます。
+    /// 
+    pub fn canonical_key(&self, turn: PieceType) -> (u64, Symmetry) {
+        let side_key = zobrist::side_key(turn);
+        Symmetry::all_for(self.x_size, self.y_size)
+            .map(|symmetry| (self.hashes[symmetry as usize] ^ side_key, symmetry))
+            .min_by_key(|&(hash, _)| hash)
+            .unwrap_or((self.position_key(turn), Symmetry::Identity))
+    }
+
+    /// 対称変換した盤面を返します。
+    /// 
+    /// 縦横の大きさが違う盤面に、正方形の盤面でしか使えない対称変換を渡すとパニックします。
+    /// 
+    pub fn transform(&self, symmetry: Symmetry) -> Board {
+        assert!(symmetry.is_valid_for(self.x_size, self.y_size), "{:?} is not valid for a {}x{} board", symmetry, self.x_size, self.y_size);
+
+        let mut board = self.clone();
+        for point in self.inner_points() {
+            let tile_type = match self.piece_at(&point) {
+                Some(piece_type) => TileType::Piece(piece_type),
+                None => TileType::Square,
+            };
+            board.tiles.set(symmetry.apply(&point, self.x_size, self.y_size), tile_type);
+        }
+        board.hashes = board.compute_hashes();
+        board
+    }
+
+    fn compute_hashes(&self) -> [u64; 8] {
+        let mut hashes = [0; 8];
+        for point in self.inner_points() {
+            if let Some(piece_type) = self.piece_at(&point) {
+                toggle_hashes(&mut hashes, &point, piece_type, self.x_size, self.y_size);
+            }
+        }
+        hashes
+    }
+
+    fn toggle_hash(&mut self, point: &Point, piece_type: PieceType) {
+        toggle_hashes(&mut self.hashes, point, piece_type, self.x_size, self.y_size);
+    }
+
+    // 白黒どちらに返した場合も同じ計算になる
+    fn flip_hash(&mut self, point: &Point) {
+        self.toggle_hash(point, PieceType::Black);
+        self.toggle_hash(point, PieceType::White);
+    }
+
+    /// 盤面の横の大きさを返します。
+    /// 
+    pub fn x_size(&self) -> usize {
+        self.x_size
+    }
+
+    /// 盤面の縦の大きさを返します。
+    /// 
+    pub fn y_size(&self) -> usize {
+        self.y_size
+    }
+
+    /// 8x8の盤面をビットボードで保持している場合はビットボードを返します。
+    /// 
+    pub(crate) fn bitboard(&self) -> Option<&Bitboard> {
+        self.tiles.bitboard()
+    }
+
+    /// "XO--..."のような盤面の文字列と手番から盤面を生成します。
+    /// 
+    /// 盤面は左上から行ごとに`x_size * y_size`文字で、`X`が黒、`O`が白、`-`が空きを表します。
+    /// 続けて空白と手番(`X`または`O`)を指定します。EdaxやFFOのテスト問題と同じ形式です。
+    /// 
+    pub fn from_position_str(s: &str, x_size: usize, y_size: usize) -> Result<(Board, PieceType), ParseBoardError> {
+        let mut board = Board::new(x_size, y_size);
+        let s = s.trim();
+
+        let (position, turn) = match s.rsplit_once(char::is_whitespace) {
+            Some((position, turn)) => (position.trim_end(), turn),
+            None => return Err(ParseBoardError::MissingTurn),
+        };
+
+        let tiles = position.chars()
+            .map(|c| match c {
+                'X' | 'x' | '*' => Ok(TileType::Piece(PieceType::Black)),
+                'O' | 'o' => Ok(TileType::Piece(PieceType::White)),
+                '-' | '.' => Ok(TileType::Square),
+                _ => Err(ParseBoardError::InvalidCharacter(c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if tiles.len() != x_size * y_size {
+            return Err(ParseBoardError::InvalidLength {
+                expected: x_size * y_size,
+                found: tiles.len(),
+            });
         }
+
+        let turn = match turn {
+            "X" | "x" | "*" => PieceType::Black,
+            "O" | "o" => PieceType::White,
+            _ => return Err(ParseBoardError::InvalidTurn(turn.to_string())),
+        };
+
+        for (point, tile_type) in board.inner_points().collect::<Vec<_>>().into_iter().zip(tiles) {
+            board.tiles.set(point, tile_type);
+        }
+        board.hashes = board.compute_hashes();
+
+        Ok((board, turn))
+    }
+
+    /// 盤面と手番を`from_position_str`で読み込める文字列にします。
+    /// 
+    pub fn to_position_str(&self, turn: PieceType) -> String {
+        let mut result = self.inner_points()
+            .map(|point| match self.get_tile(&point) {
+                Some(TileType::Piece(PieceType::Black)) => 'X',
+                Some(TileType::Piece(PieceType::White)) => 'O',
+                _ => '-',
+            })
+            .collect::<String>();
+
+        result.push(' ');
+        result.push(match turn {
+            PieceType::Black => 'X',
+            PieceType::White => 'O',
+        });
+        result
     }
 
     /// コンソール画面に盤面を表示します。
@@ -46,24 +217,30 @@ impl Board {
     /// ゲームが終了していたらtrueを返します。
     /// 
     pub fn is_end(&self) -> bool {
-        self.is_end_nosquare() || self.is_end_one_color()
+        self.end_reason().is_some()
+    }
+
+    /// ゲームが終了した理由を返します。
+    /// ゲームが終了していない場合はNoneが返却されます。
+    /// 
+    pub fn end_reason(&self) -> Option<EndReason> {
+        if self.is_end_nosquare() {
+            Some(EndReason::BoardFull)
+        } else if self.is_end_one_color() {
+            Some(EndReason::WipeOut)
+        } else if self.is_skip(PieceType::Black) && self.is_skip(PieceType::White) {
+            Some(EndReason::MutualPass)
+        } else {
+            None
+        }
     }
 
     /// ゲームの勝者を返却します。
     /// ゲームが終了していない場合はNoneが返却されます。
     /// 
     pub fn get_winner(&self) -> Option<PieceType> {
-        // 白または黒一色か？
-        let mut white_count = 0;
-        let mut black_count = 0;
-
-        for tile_type in self.tiles.iter_tile() {
-            match tile_type {
-                TileType::Piece(PieceType::White) => white_count += 1,
-                TileType::Piece(PieceType::Black) => black_count += 1,
-                _ => (),
-            }
-        }
+        let white_count = self.disc_count(PieceType::White);
+        let black_count = self.disc_count(PieceType::Black);
 
         match white_count.cmp(&black_count) {
             Ordering::Greater => Some(PieceType::White),
@@ -72,20 +249,122 @@ impl Board {
         }
     }
 
+    /// 盤面にある指定した駒の数を返します。
+    /// 
+    pub fn disc_count(&self, piece_type: PieceType) -> usize {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return bitboard.pieces(piece_type).count_ones() as usize;
+        }
+
+        self.tiles.iter_tile()
+            .filter(|&&t| t == TileType::Piece(piece_type))
+            .count()
+    }
+
+    /// 盤面の空いているマスの数を返します。
+    /// 
+    pub fn empty_count(&self) -> usize {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return bitboard.empties().count_ones() as usize;
+        }
+
+        self.tiles.iter_tile()
+            .filter(|&&t| t == TileType::Square)
+            .count()
+    }
+
+    /// 指定した位置にある駒を返します。駒が無い場所や盤外の場合はNoneが返却されます。
+    /// 
+    pub fn piece_at(&self, point: &Point) -> Option<PieceType> {
+        match self.get_tile(point) {
+            Some(TileType::Piece(piece_type)) => Some(*piece_type),
+            _ => None,
+        }
+    }
+
+    /// 空いているマスに隣接している指定した駒(開放石)の数を返します。
+    /// 
+    pub fn frontier_count(&self, piece_type: PieceType) -> usize {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            let frontier = Bitboard::neighbors(bitboard.empties()) & bitboard.pieces(piece_type);
+            return frontier.count_ones() as usize;
+        }
+
+        self.inner_points()
+            .filter(|point| self.piece_at(point) == Some(piece_type))
+            .filter(|point| self.neighbors(point.x, point.y).iter().any(|t| matches!(t, Some(TileType::Square))))
+            .count()
+    }
+
+    /// 相手の駒に隣接している空きマス(潜在的に打てる場所)の数を返します。
+    /// 
+    pub fn potential_mobility(&self, piece_type: PieceType) -> usize {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            let potential = Bitboard::neighbors(bitboard.pieces(piece_type.change())) & bitboard.empties();
+            return potential.count_ones() as usize;
+        }
+
+        let opponent = TileType::Piece(piece_type.change());
+        self.inner_points()
+            .filter(|point| self.is_square(point))
+            .filter(|point| self.neighbors(point.x, point.y).contains(&Some(&opponent)))
+            .count()
+    }
+
+    /// `piece_type`から見た終局時の石差を返します。
+    /// 空いているマスは勝った側の駒として数えます(引き分けの場合は数えません)。
+    /// 
+    pub fn final_score(&self, piece_type: PieceType) -> i32 {
+        let own = self.disc_count(piece_type) as i32;
+        let opponent = self.disc_count(piece_type.change()) as i32;
+        let empties = self.empty_count() as i32;
+
+        match own.cmp(&opponent) {
+            Ordering::Greater => own - opponent + empties,
+            Ordering::Less => own - opponent - empties,
+            Ordering::Equal => 0,
+        }
+    }
+
     /// 駒が打てない場合にtrueを返します。
     /// 
     pub fn is_skip(&self, piece_type: PieceType) -> bool {
-        let mut result = true;
-
-        for point in self.tiles.iter_point() {
-            if self.can_put_piece(piece_type, &point) {
-                result = false;
-                break;
-            }
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return bitboard.legal_moves(piece_type) == 0;
         }
 
         // 置ける場所が無い場合
-        result
+        !self.inner_points().any(|point| self.can_put_piece(piece_type, &point))
+    }
+
+    /// 駒を置ける位置を全て返します。
+    /// 盤面の左上から行ごとに並んだ順で返却されます。
+    /// 
+    pub fn legal_moves(&self, piece_type: PieceType) -> Vec<Point> {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return Bitboard::points(bitboard.legal_moves(piece_type)).collect();
+        }
+
+        self.inner_points()
+            .filter(|point| self.can_put_piece(piece_type, point))
+            .collect()
+    }
+
+    /// 駒を置ける位置の数を返します。
+    /// 
+    pub fn legal_move_count(&self, piece_type: PieceType) -> usize {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return bitboard.legal_moves(piece_type).count_ones() as usize;
+        }
+
+        self.inner_points()
+            .filter(|point| self.can_put_piece(piece_type, point))
+            .count()
+    }
+
+    fn inner_points(&self) -> impl Iterator<Item = Point> {
+        let x_size = self.x_size;
+        (1..=self.y_size).flat_map(move |y| (1..=x_size).map(move |x| Point::new(x, y)))
     }
 
     fn is_end_nosquare(&self) -> bool {
@@ -96,27 +375,20 @@ impl Board {
 
     fn is_end_one_color(&self) -> bool {
         // 白または黒一色か？
-        let mut white_count = 0;
-        let mut black_count = 0;
-
-        for tile in self.tiles.iter_tile() {
-            match tile {
-                TileType::Piece(PieceType::White) => white_count += 1,
-                TileType::Piece(PieceType::Black) => black_count += 1,
-                _ => (),
-            }
-            if (white_count != 0) && (black_count != 0) {
-                return false;
-            }
-        }
-
-        true
+        self.disc_count(PieceType::White) == 0 || self.disc_count(PieceType::Black) == 0
     }
 
     /// 置こうとした場所に駒が置けるか判定します。
     /// `Point`構造体の位置に駒が置ける場合はtrueを返します。
     /// 
     pub fn can_put_piece(&self, piece_type: PieceType, point: &Point) -> bool {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return match Bitboard::index(point) {
+                Some(index) => bitboard.flips(piece_type, index) != 0,
+                None => false,
+            };
+        }
+
         // 置こうとした場所は有効な場所か？
         if self.is_square(point) {
             // 隣接してない所は置けない
@@ -136,30 +408,136 @@ impl Board {
     /// 置いたコマによって既に置かれた駒が変わるため、新しい`Board`構造体が返却されます。
     /// 
     pub fn put_piece(self, piece_type: PieceType, point: Point) -> Board {
-        // 置こうとした場所は有効な場所か？
-        let lines = if self.is_square(&point) {
-            if self.is_next_to_piece(point.x, point.y) {
-                self.can_change_piece_line(piece_type, point.x, point.y)
-            } else {
-                Vec::new()
+        let mut board = self;
+        board.make_move(piece_type, point);
+        board
+    }
+
+    /// 駒を置きます。
+    /// 置けない場所の場合は置けなかった理由を`MoveError`で返却します。
+    /// 
+    pub fn try_put_piece(self, piece_type: PieceType, point: Point) -> Result<Board, MoveError> {
+        self.check_move(piece_type, &point)?;
+        Ok(self.put_piece(piece_type, point))
+    }
+
+    /// 置こうとした場所に駒が置けるか判定します。
+    /// 置けない場合は置けなかった理由を`MoveError`で返却します。
+    /// 
+    pub fn check_move(&self, piece_type: PieceType, point: &Point) -> Result<(), MoveError> {
+        match self.get_tile(point) {
+            Some(TileType::Square) => (),
+            Some(TileType::Piece(_)) => return Err(MoveError::Occupied(*point)),
+            _ => return Err(MoveError::OutOfBounds(*point)),
+        }
+
+        if !self.is_next_to_piece(point.x, point.y) {
+            Err(MoveError::NotAdjacent(*point))
+        } else if !self.can_put_piece(piece_type, point) {
+            Err(MoveError::NoFlips(*point))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 駒を置き、返った駒の位置と共に新しい`Board`構造体を返却します。
+    /// 置けない場所の場合は盤面は変わらず、返った駒の位置は空になります。
+    /// 
+    pub fn put_piece_with_flips(self, piece_type: PieceType, point: Point) -> (Board, Vec<Point>) {
+        let mut board = self;
+        let undo = board.make_move(piece_type, point);
+        (board, undo.flipped())
+    }
+
+    /// 盤面をそのまま変更して駒を置きます。
+    /// 返却された`MoveUndo`を`unmake_move`に渡すと置く前の盤面に戻せます。
+    /// 置けない場所の場合は盤面は変わらず、空の`MoveUndo`が返却されます。
+    /// 
+    pub fn make_move(&mut self, piece_type: PieceType, point: Point) -> MoveUndo {
+        let flips = if let Some(bitboard) = self.tiles.bitboard_mut() {
+            let flips = match Bitboard::index(&point) {
+                Some(index) => {
+                    let flips = bitboard.flips(piece_type, index);
+                    if flips != 0 {
+                        bitboard.apply(piece_type, index, flips);
+                    }
+                    flips
+                }
+                None => 0,
+            };
+            if flips != 0 {
+                self.toggle_hash(&point, piece_type);
+                self.update_flip_hash(flips);
             }
+            Flips::Bits(flips)
         } else {
-            Vec::new()
+            let flips = self.flips(piece_type, &point);
+            if !flips.is_empty() {
+                self.tiles.set(point, TileType::Piece(piece_type));
+                self.toggle_hash(&point, piece_type);
+                self.change_tiles(&flips, piece_type);
+            }
+            Flips::Points(flips)
         };
-        
-        let mut board = self;
 
-        if !lines.is_empty() {
-            board.tiles.set(point, TileType::Piece(piece_type));
+        MoveUndo {
+            piece_type,
+            point,
+            flips,
+        }
+    }
+
+    /// `make_move`で置いた駒を取り除き、返った駒を全て元に戻します。
+    /// 
+    pub fn unmake_move(&mut self, undo: MoveUndo) {
+        if undo.is_empty() {
+            return;
+        }
 
-            for line in lines {
-                let tiles = Board::can_change_tiles(&line, piece_type);
-                let tiles = tiles.iter().map(|v| v.0).collect::<Vec<_>>();
-                board = board.change_tiles(&tiles, piece_type);
+        match (self.tiles.bitboard_mut(), &undo.flips, Bitboard::index(&undo.point)) {
+            (Some(bitboard), Flips::Bits(flips), Some(index)) => {
+                bitboard.restore(undo.piece_type, index, *flips);
+                self.update_flip_hash(*flips);
+            }
+            _ => {
+                self.tiles.set(undo.point, TileType::Square);
+                self.change_tiles(&undo.flipped(), undo.piece_type.change());
             }
         }
+        self.toggle_hash(&undo.point, undo.piece_type);
+    }
 
-        board
+    // 返った駒の分だけハッシュ値を更新する
+    fn update_flip_hash(&mut self, flips: u64) {
+        for point in Bitboard::points(flips) {
+            self.flip_hash(&point);
+        }
+    }
+
+    /// `Point`構造体の位置に駒を置いた時に返る駒の位置を返します。
+    /// 盤面の左上から行ごとに並んだ順で返却され、置けない場所の場合は空になります。
+    /// 
+    pub fn flips(&self, piece_type: PieceType, point: &Point) -> Vec<Point> {
+        if let Some(bitboard) = self.tiles.bitboard() {
+            return match Bitboard::index(point) {
+                Some(index) => Bitboard::points(bitboard.flips(piece_type, index)).collect(),
+                None => Vec::new(),
+            };
+        }
+
+        // 置こうとした場所は有効な場所か？
+        if !self.is_square(point) || !self.is_next_to_piece(point.x, point.y) {
+            return Vec::new();
+        }
+
+        let mut flips = self.can_change_piece_line(piece_type, point.x, point.y)
+            .iter()
+            .flat_map(|line| Board::can_change_tiles(line, piece_type))
+            .map(|v| v.0)
+            .collect::<Vec<_>>();
+        flips.sort_by_key(|p| (p.y, p.x));
+
+        flips
     }
 
     fn is_square(&self, point: &Point) -> bool {
@@ -167,16 +545,20 @@ impl Board {
     }
 
     fn is_next_to_piece(&self, x: usize, y: usize) -> bool {
-        let target = [
-            self.get_tile((x - 1, y - 1)), self.get_tile((x, y - 1)), self.get_tile((x + 1, y - 1)),
-            self.get_tile((x - 1, y)), self.get_tile((x + 1, y)),
-            self.get_tile((x - 1, y + 1)), self.get_tile((x, y + 1)), self.get_tile((x + 1, y + 1)),
-            ];
+        let target = self.neighbors(x, y);
 
         // 隣接する場所に駒があるか
         target.iter().any(|t| matches!(t, Some(TileType::Piece(_))))
     }
 
+    fn neighbors(&self, x: usize, y: usize) -> [Option<&TileType>; 8] {
+        [
+            self.get_tile((x - 1, y - 1)), self.get_tile((x, y - 1)), self.get_tile((x + 1, y - 1)),
+            self.get_tile((x - 1, y)), self.get_tile((x + 1, y)),
+            self.get_tile((x - 1, y + 1)), self.get_tile((x, y + 1)), self.get_tile((x + 1, y + 1)),
+        ]
+    }
+
     fn can_change_piece_line(&self, piece_type: PieceType, x: usize, y: usize) -> Vec<Vec<(Point, PieceType)>> {
 
         let targets = [
@@ -216,16 +598,10 @@ impl Board {
         result
     }
 
-    fn change_tiles(self, change_points: &[Point], piece_type: PieceType) -> Board {
-        let mut tiles = self.tiles;
-
+    fn change_tiles(&mut self, change_points: &[Point], piece_type: PieceType) {
         for point in change_points {
-            tiles.set(*point, TileType::Piece(piece_type));
-        }
-
-        Board {
-            tiles,
-            ..self
+            self.tiles.set(*point, TileType::Piece(piece_type));
+            self.flip_hash(point);
         }
     }
 
@@ -293,6 +669,40 @@ impl XYPoint<(usize, usize)> for Board {
     }
 }
 
+/// 盤面の文字列を読み取れなかった理由
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseBoardError {
+    /// 盤面の文字数が盤面の大きさと合わない
+    InvalidLength { expected: usize, found: usize },
+    /// 盤面に使えない文字が含まれている
+    InvalidCharacter(char),
+    /// 手番が指定されていない
+    MissingTurn,
+    /// 手番が`X`でも`O`でもない
+    InvalidTurn(String),
+}
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBoardError::InvalidLength { expected, found } => write!(f, "盤面の文字数が不正です : {}文字必要ですが{}文字です", expected, found),
+            ParseBoardError::InvalidCharacter(c) => write!(f, "盤面に使えない文字です : {}", c),
+            ParseBoardError::MissingTurn => write!(f, "手番が指定されていません"),
+            ParseBoardError::InvalidTurn(turn) => write!(f, "手番の値が不正です : {}", turn),
+        }
+    }
+}
+
+impl Error for ParseBoardError {}
+
+// `point`の駒を対称変換した位置の乱数を、変換ごとのハッシュ値に反映する。
+// 縦横の大きさが違う盤面で使えない変換の値は使われない
+fn toggle_hashes(hashes: &mut [u64; 8], point: &Point, piece_type: PieceType, x_size: usize, y_size: usize) {
+    for (hash, symmetry) in hashes.iter_mut().zip(Symmetry::ALL) {
+        *hash ^= zobrist::piece_key(&symmetry.apply(point, x_size, y_size), piece_type);
+    }
+}
 
 
 #[cfg(test)]
@@ -327,7 +737,7 @@ mod tests {
         }
 
         // 白は置けない
-        assert_eq!(board.is_skip(PieceType::White), true);
+        assert!(board.is_skip(PieceType::White));
     }
 
     #[test]
@@ -368,7 +778,7 @@ mod tests {
         board.tiles.set(Point::new(8, 6), TileType::Square);
 
         // 黒は置ける
-        assert_eq!(false, board.is_skip(PieceType::Black));
+        assert!(!board.is_skip(PieceType::Black));
     }
 
     #[test]
@@ -388,16 +798,16 @@ mod tests {
         // 9|×|×|×|×|×|×|×|×|×|×|
 
         // 1,1 に白も黒も置けない
-        assert_eq!(board.can_put_piece(PieceType::White, &Point::new(1, 1)), false);
-        assert_eq!(board.can_put_piece(PieceType::Black, &Point::new(1, 1)), false);
+        assert!(!board.can_put_piece(PieceType::White, &Point::new(1, 1)));
+        assert!(!board.can_put_piece(PieceType::Black, &Point::new(1, 1)));
 
         // 3,4 に白は置けない、黒は置ける
-        assert_eq!(board.can_put_piece(PieceType::White, &Point::new(3, 4)), false);
-        assert_eq!(board.can_put_piece(PieceType::Black, &Point::new(3, 4)), true);
+        assert!(!board.can_put_piece(PieceType::White, &Point::new(3, 4)));
+        assert!(board.can_put_piece(PieceType::Black, &Point::new(3, 4)));
 
         // 5,3 に白は置ける、黒は置けない
-        assert_eq!(board.can_put_piece(PieceType::White, &Point::new(5, 3)), true);
-        assert_eq!(board.can_put_piece(PieceType::Black, &Point::new(5, 3)), false);
+        assert!(board.can_put_piece(PieceType::White, &Point::new(5, 3)));
+        assert!(!board.can_put_piece(PieceType::Black, &Point::new(5, 3)));
     }
 
     #[test]
@@ -437,7 +847,7 @@ mod tests {
         board.tiles.set(Point::new(4, 6), TileType::Square);
         board.tiles.set(Point::new(8, 6), TileType::Square);
 
-        assert_eq!(true, board.can_put_piece(PieceType::Black, &Point::new(8,6)));
+        assert!(board.can_put_piece(PieceType::Black, &Point::new(8,6)));
     }
 
     #[test]
@@ -525,7 +935,7 @@ mod tests {
         }
 
         // タイルが無い
-        assert_eq!(true, board.is_end_nosquare());
+        assert!(board.is_end_nosquare());
     }
 
     #[test]
@@ -552,7 +962,7 @@ mod tests {
         board.tiles.set(Point::new(1, 1), TileType::Square);
 
         // タイルが無い
-        assert_eq!(false, board.is_end_nosquare());
+        assert!(!board.is_end_nosquare());
     }
 
     #[test]
@@ -574,14 +984,14 @@ mod tests {
         // 黒一色
         board.tiles.set(Point::new(4, 4), TileType::Piece(PieceType::Black));
         board.tiles.set(Point::new(5, 5), TileType::Piece(PieceType::Black));
-        assert_eq!(true, board.is_end_one_color());
+        assert!(board.is_end_one_color());
 
         // 白一色
         board.tiles.set(Point::new(4, 4), TileType::Piece(PieceType::White));
         board.tiles.set(Point::new(4, 5), TileType::Piece(PieceType::White));
         board.tiles.set(Point::new(5, 4), TileType::Piece(PieceType::White));
         board.tiles.set(Point::new(5, 5), TileType::Piece(PieceType::White));
-        assert_eq!(true, board.is_end_one_color());
+        assert!(board.is_end_one_color());
     }
 
     #[test]
@@ -602,32 +1012,385 @@ mod tests {
         // 9|×|×|×|×|×|×|×|×|×|×|
 
         // どちらか一色でもない
-        assert_eq!(false, board.is_end_one_color());
+        assert!(!board.is_end_one_color());
     }
 
     #[test]
     fn test_is_next_to_piece() {
         let mut board = Board::new(8, 8);
 
-        assert_eq!(false, board.is_next_to_piece(2, 4));
-        assert_eq!(true, board.is_next_to_piece(3, 4));
+        assert!(!board.is_next_to_piece(2, 4));
+        assert!(board.is_next_to_piece(3, 4));
         
         board.tiles.set(Point::new(3, 4), TileType::Piece(PieceType::White));
         
-        assert_eq!(false, board.is_next_to_piece(1, 4));
-        assert_eq!(true, board.is_next_to_piece(2, 4));
+        assert!(!board.is_next_to_piece(1, 4));
+        assert!(board.is_next_to_piece(2, 4));
     }
 
     #[test]
     fn test_can_change_piece_line() {
         let board = Board::new(8, 8);
 
-        assert_eq!(false, board.can_change_piece_line(PieceType::Black, 2, 4).len() > 0);
-        assert_eq!(true, board.can_change_piece_line(PieceType::Black, 3, 4).len() > 0);
+        assert!(board.can_change_piece_line(PieceType::Black, 2, 4).is_empty());
+        assert!(!board.can_change_piece_line(PieceType::Black, 3, 4).is_empty());
 
         let board = board.put_piece(PieceType::Black, Point::new(3, 4));
 
-        assert_eq!(false, board.can_change_piece_line(PieceType::White, 3, 2).len() > 0);
-        assert_eq!(true, board.can_change_piece_line(PieceType::White, 3, 3).len() > 0);
+        assert!(board.can_change_piece_line(PieceType::White, 3, 2).is_empty());
+        assert!(!board.can_change_piece_line(PieceType::White, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn test_bitboard_matches_general() {
+        let mut board = Board::new(8, 8);
+        let mut general = Board::new_general(8, 8);
+        let mut piece_type = PieceType::Black;
+        let mut turn = 0;
+
+        // ビットボードとHashMapの盤面で同じ手を打ち続けて結果を比較する
+        while !general.is_end() {
+            if general.is_skip(piece_type) && general.is_skip(piece_type.change()) {
+                break;
+            }
+            assert_eq!(general.is_skip(piece_type), board.is_skip(piece_type));
+
+            let points = general.inner_points()
+                .filter(|p| general.can_put_piece(piece_type, p))
+                .collect::<Vec<_>>();
+            for point in general.inner_points() {
+                assert_eq!(general.can_put_piece(piece_type, &point), board.can_put_piece(piece_type, &point));
+            }
+
+            if !points.is_empty() {
+                let point = points[(turn * 7) % points.len()];
+                board = board.put_piece(piece_type, point);
+                general = general.put_piece(piece_type, point);
+            }
+            for point in general.inner_points() {
+                assert!(general.tiles.get(&point) == board.tiles.get(&point));
+            }
+            assert_eq!(general.compute_hashes(), general.hashes);
+            assert_eq!(board.compute_hashes(), board.hashes);
+            assert_eq!(general.zobrist_hash(), board.zobrist_hash());
+
+            piece_type = piece_type.change();
+            turn += 1;
+        }
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        let board = Board::new(8, 8);
+
+        assert_eq!(
+            vec![Point::new(4, 3), Point::new(3, 4), Point::new(6, 5), Point::new(5, 6)],
+            board.legal_moves(PieceType::Black)
+        );
+        assert_eq!(4, board.legal_move_count(PieceType::White));
+    }
+
+    #[test]
+    fn test_legal_moves_general() {
+        let board = Board::new(6, 6);
+
+        assert_eq!(
+            vec![Point::new(3, 2), Point::new(2, 3), Point::new(5, 4), Point::new(4, 5)],
+            board.legal_moves(PieceType::Black)
+        );
+        assert_eq!(4, board.legal_move_count(PieceType::White));
+    }
+
+    #[test]
+    fn test_flips() {
+        let board = Board::new(8, 8);
+
+        assert_eq!(vec![Point::new(4, 4)], board.flips(PieceType::Black, &Point::new(4, 3)));
+        assert_eq!(Vec::<Point>::new(), board.flips(PieceType::Black, &Point::new(1, 1)));
+        assert_eq!(Vec::<Point>::new(), board.flips(PieceType::Black, &Point::new(4, 4)));
+    }
+
+    #[test]
+    fn test_put_piece_with_flips() {
+        for size in [6, 8] {
+            let board = Board::new(size, size);
+            let center = size / 2;
+
+            let (board, flips) = board.put_piece_with_flips(PieceType::Black, Point::new(center, center - 1));
+            assert_eq!(vec![Point::new(center, center)], flips);
+            assert!(board.get_tile(&Point::new(center, center)) == Some(&TileType::Piece(PieceType::Black)));
+
+            // 置けない場所は盤面が変わらない
+            let (board, flips) = board.put_piece_with_flips(PieceType::White, Point::new(1, 1));
+            assert!(flips.is_empty());
+            assert!(board.get_tile(&Point::new(1, 1)) == Some(&TileType::Square));
+        }
+    }
+
+    #[test]
+    fn test_make_move_and_unmake_move() {
+        for size in [6, 8] {
+            let mut board = Board::new(size, size);
+            let original = board.clone();
+            let center = size / 2;
+
+            let undo = board.make_move(PieceType::Black, Point::new(center, center - 1));
+            assert_eq!(vec![Point::new(center, center)], undo.flipped());
+            assert!(board != original);
+
+            let undo2 = board.make_move(PieceType::White, Point::new(center - 1, center - 1));
+            assert_eq!(1, undo2.flipped().len());
+
+            board.unmake_move(undo2);
+            board.unmake_move(undo);
+            assert!(board == original);
+        }
+    }
+
+    #[test]
+    fn test_make_move_illegal() {
+        let mut board = Board::new(8, 8);
+        let original = board.clone();
+
+        // 置けない場所は盤面が変わらず、戻しても変わらない
+        let undo = board.make_move(PieceType::Black, Point::new(1, 1));
+        assert!(undo.is_empty());
+        board.unmake_move(undo);
+        assert!(board == original);
+    }
+
+    #[test]
+    fn test_board_hash() {
+        use std::collections::HashSet;
+
+        // 同じ盤面は同じものとして扱われる
+        let mut set = HashSet::new();
+        for size in [6, 8] {
+            let board = Board::new(size, size);
+            set.insert(board.clone());
+            set.insert(board);
+        }
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn test_try_put_piece() {
+        for size in [6, 8] {
+            let board = Board::new(size, size);
+            let center = size / 2;
+
+            assert_eq!(Err(MoveError::OutOfBounds(Point::new(0, 1))), board.check_move(PieceType::Black, &Point::new(0, 1)));
+            assert_eq!(Err(MoveError::OutOfBounds(Point::new(size + 5, 1))), board.check_move(PieceType::Black, &Point::new(size + 5, 1)));
+            assert_eq!(Err(MoveError::Occupied(Point::new(center, center))), board.check_move(PieceType::Black, &Point::new(center, center)));
+            assert_eq!(Err(MoveError::NotAdjacent(Point::new(1, 1))), board.check_move(PieceType::Black, &Point::new(1, 1)));
+            assert_eq!(Err(MoveError::NoFlips(Point::new(center - 1, center - 1))), board.check_move(PieceType::Black, &Point::new(center - 1, center - 1)));
+
+            let board = board.try_put_piece(PieceType::Black, Point::new(center, center - 1)).ok().unwrap();
+            assert_eq!(PieceType::Black, board.get_winner().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_end_reason_mutual_pass() {
+        let mut board = Board::new(4, 4);
+        let mut piece_type = PieceType::Black;
+
+        //  |0|1|2|3|4|5|
+        // 0|×|×|×|×|×|×|
+        // 1|×|○|○|○| |×|
+        // 2|×|○|○|○|●|×|
+        // 3|×|○|○|○|●|×|
+        // 4|×| |●| |●|×|
+        // 5|×|×|×|×|×|×|
+        for (x, y) in [(2, 1), (1, 1), (1, 2), (3, 1), (4, 2), (1, 3), (2, 4), (4, 3), (4, 4)] {
+            assert_eq!(None, board.end_reason());
+            board = board.put_piece(piece_type, Point::new(x, y));
+            piece_type = piece_type.change();
+        }
+
+        // 空きマスはあるが両者とも打てない
+        assert!(!board.is_end_nosquare());
+        assert_eq!(Some(EndReason::MutualPass), board.end_reason());
+        assert!(board.is_end());
+    }
+
+    #[test]
+    fn test_end_reason() {
+        let mut board = Board::new(8, 8);
+        assert_eq!(None, board.end_reason());
+
+        board.tiles.set(Point::new(4, 4), TileType::Piece(PieceType::Black));
+        board.tiles.set(Point::new(5, 5), TileType::Piece(PieceType::Black));
+        assert_eq!(Some(EndReason::WipeOut), board.end_reason());
+
+        for x in 1..=8 {
+            for y in 1..=8 {
+                board.tiles.set(Point::new(x, y), TileType::Piece(PieceType::White));
+            }
+        }
+        board.tiles.set(Point::new(1, 1), TileType::Piece(PieceType::Black));
+        assert_eq!(Some(EndReason::BoardFull), board.end_reason());
+    }
+
+    #[test]
+    fn test_disc_count() {
+        for size in [6, 8] {
+            let board = Board::new(size, size);
+            assert_eq!(2, board.disc_count(PieceType::Black));
+            assert_eq!(2, board.disc_count(PieceType::White));
+            assert_eq!(size * size - 4, board.empty_count());
+
+            let board = board.put_piece(PieceType::Black, Point::new(size / 2, size / 2 - 1));
+            assert_eq!(4, board.disc_count(PieceType::Black));
+            assert_eq!(1, board.disc_count(PieceType::White));
+            assert_eq!(size * size - 5, board.empty_count());
+        }
+    }
+
+    #[test]
+    fn test_final_score() {
+        let mut board = Board::new(4, 4);
+        let mut piece_type = PieceType::Black;
+
+        //  |0|1|2|3|4|5|
+        // 0|×|×|×|×|×|×|
+        // 1|×|○|○|○| |×|
+        // 2|×|○|○|○|●|×|
+        // 3|×|○|○|○|●|×|
+        // 4|×| |●| |●|×|
+        // 5|×|×|×|×|×|×|
+        for (x, y) in [(2, 1), (1, 1), (1, 2), (3, 1), (4, 2), (1, 3), (2, 4), (4, 3), (4, 4)] {
+            board = board.put_piece(piece_type, Point::new(x, y));
+            piece_type = piece_type.change();
+        }
+
+        // 白9 黒4 空き3 は空きを白に加えて 12 - 4
+        assert_eq!(8, board.final_score(PieceType::White));
+        assert_eq!(-8, board.final_score(PieceType::Black));
+
+        // 同数は0
+        assert_eq!(0, Board::new(8, 8).final_score(PieceType::Black));
+    }
+
+    #[test]
+    fn test_from_position_str() {
+        //  |0|1|2|3|4|5|6|7|8|9|
+        // 0|×|×|×|×|×|×|×|×|×|×|
+        // 1|×| | | | | | | | |×|
+        // 2|×| | | | | | | | |×|
+        // 3|×| | | |●| | | | |×|
+        // 4|×| | | |●|●| | | |×|
+        // 5|×| | | |●|○| | | |×|
+        // 6|×| | | | | | | | |×|
+        // 7|×| | | | | | | | |×|
+        // 8|×| | | | | | | | |×|
+        // 9|×|×|×|×|×|×|×|×|×|×|
+        let position = "-------- -------- ---X---- ---XX--- ---XO--- -------- -------- --------".replace(' ', "") + " O";
+        let (board, turn) = Board::from_position_str(&position, 8, 8).ok().unwrap();
+
+        assert!(board == Board::new(8, 8).put_piece(PieceType::Black, Point::new(4, 3)));
+        assert_eq!(PieceType::White, turn);
+        assert_eq!(position, board.to_position_str(turn));
+    }
+
+    #[test]
+    fn test_position_str_round_trip() {
+        let board = Board::new(6, 4).put_piece(PieceType::Black, Point::new(3, 1));
+        let position = board.to_position_str(PieceType::White);
+        assert_eq!("--X-----XX----XO-------- O", position);
+
+        let (restored, turn) = Board::from_position_str(&position, 6, 4).ok().unwrap();
+        assert!(restored == board);
+        assert_eq!(PieceType::White, turn);
+    }
+
+    #[test]
+    fn test_from_position_str_error() {
+        let empty = "-".repeat(64);
+
+        assert_eq!(Some(ParseBoardError::MissingTurn), Board::from_position_str(&empty, 8, 8).err());
+        assert_eq!(Some(ParseBoardError::InvalidTurn("Z".to_string())), Board::from_position_str(&format!("{} Z", empty), 8, 8).err());
+        assert_eq!(Some(ParseBoardError::InvalidLength { expected: 36, found: 64 }), Board::from_position_str(&format!("{} X", empty), 6, 6).err());
+        assert_eq!(Some(ParseBoardError::InvalidCharacter('#')), Board::from_position_str(&format!("#{} X", &empty[1..]), 8, 8).err());
+    }
+
+    #[test]
+    fn test_frontier_and_potential_mobility() {
+        for size in [6, 8] {
+            let board = Board::new(size, size);
+            assert_eq!(Some(PieceType::White), board.piece_at(&Point::new(size / 2, size / 2)));
+            assert_eq!(None, board.piece_at(&Point::new(1, 1)));
+            assert_eq!(None, board.piece_at(&Point::new(0, 0)));
+
+            // 初期配置は全ての駒が空きマスに隣接している
+            assert_eq!(2, board.frontier_count(PieceType::Black));
+            assert_eq!(10, board.potential_mobility(PieceType::Black));
+
+            let board = board.put_piece(PieceType::Black, Point::new(size / 2, size / 2 - 1));
+            assert_eq!(4, board.frontier_count(PieceType::Black));
+            assert_eq!(5, board.potential_mobility(PieceType::Black));
+            assert_eq!(13, board.potential_mobility(PieceType::White));
+        }
+    }
+
+    #[test]
+    fn test_zobrist_hash_make_and_unmake() {
+        for size in [6, 8] {
+            let mut board = Board::new(size, size);
+            let original = board.zobrist_hash();
+
+            let undo = board.make_move(PieceType::Black, Point::new(size / 2, size / 2 - 1));
+            assert_ne!(original, board.zobrist_hash());
+            assert_eq!(board.compute_hashes(), board.hashes);
+
+            board.unmake_move(undo);
+            assert_eq!(original, board.zobrist_hash());
+            assert_ne!(board.position_key(PieceType::Black), board.position_key(PieceType::White));
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_canonical_key() {
+        // 初手の4手は全て対称なので同じ盤面として扱われる
+        let board = Board::new(8, 8);
+        let keys = board.legal_moves(PieceType::Black)
+            .into_iter()
+            .map(|point| board.clone().put_piece(PieceType::Black, point).canonical_key(PieceType::White).0)
+            .collect::<Vec<_>>();
+        assert!(keys.iter().all(|&key| key == keys[0]));
+
+        // 対称変換した盤面のハッシュ値が最小値になる
+        let next = board.put_piece(PieceType::Black, Point::new(4, 3));
+        let (key, symmetry) = next.canonical_key(PieceType::White);
+        assert_eq!(key, next.transform(symmetry).position_key(PieceType::White));
+
+        // 差分で更新した値と、対称変換した盤面から計算し直した値が一致する
+        let mut board = next.clone();
+        board.make_move(PieceType::White, Point::new(3, 3));
+        for (index, symmetry) in Symmetry::ALL.into_iter().enumerate() {
+            assert_eq!(board.hashes[index], board.transform(symmetry).zobrist_hash());
+        }
+
+        // 縦横の大きさが違う盤面は4通り
+        let board = Board::new(6, 4).put_piece(PieceType::Black, Point::new(3, 1));
+        let (key, symmetry) = board.canonical_key(PieceType::White);
+        assert!(symmetry.is_valid_for(6, 4));
+        assert_eq!(key, board.transform(symmetry).position_key(PieceType::White));
+    }
+
+    #[test]
+    fn test_transform_non_square() {
+        // 縦横の大きさが違う盤面でも、上下左右の反転は盤面の中に収まる
+        let board = Board::new_general(6, 4).put_piece(PieceType::Black, Point::new(3, 1));
+        let flipped = board.transform(Symmetry::FlipHorizontal);
+        assert_eq!(board.disc_count(PieceType::Black), flipped.disc_count(PieceType::Black));
+        assert!(board == flipped.transform(Symmetry::FlipHorizontal));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not valid")]
+    fn test_transform_non_square_rotate() {
+        // 90度の回転は正方形の盤面でしか使えない
+        Board::new_general(6, 4).transform(Symmetry::Rotate90);
+    }
+}
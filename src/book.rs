@@ -0,0 +1,480 @@
+//! 定石(オープニングブック)
+//! 
+//! `f5d6c3 +2`のように棋譜と黒から見た石差を並べたテキストから読み込みます。
+//! 局面は回転・反転で同じになるものをまとめて保存するため、どの向きから始めた対局でも引けます。
+//! 
+
+use crate::board::Board;
+use crate::end_reason::EndReason;
+use crate::game::Game;
+use crate::move_type::MoveType;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+use crate::rng::Rng;
+use crate::transcript::{self, TranscriptError};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// # BookMove 構造体
+/// 
+/// 定石に登録されている手
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookMove {
+    /// 置く場所
+    pub point: Point,
+    /// 手番から見た石差
+    pub score: i32,
+    /// この手を登録した回数
+    pub games: u32,
+}
+
+// 保存用の手。座標は対称変換した盤面での位置で、同じ手は打った後の局面のハッシュ値で見分ける。
+// `child`は次の手番から見た局面のハッシュ値で、相手がパスする場合は`pass`がtrueになる。
+// `learned`はこの手で終わる棋譜に登録した石差の平均で、ミニマックスの評価値`score`とは別に持つ
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    point: Point,
+    child: u64,
+    pass: bool,
+    score: i32,
+    games: u32,
+    learned: Option<i32>,
+    learned_games: u32,
+}
+
+/// # Book 構造体
+/// 
+/// 局面ごとに登録された手と評価値を持つ定石
+/// 
+/// 棋譜の最後の手には登録した石差を持ち、同じ棋譜を何度も登録した場合は平均にします。
+/// 手の評価値は、先の局面が登録されていればその局面からミニマックスで伝えた値、無ければ登録した石差です。
+/// 棋譜を登録すると、その棋譜の局面と合流する局面の評価値だけを更新します。
+/// 
+pub struct Book {
+    x_size: usize,
+    y_size: usize,
+    positions: HashMap<u64, Vec<Edge>>,
+    // 局面から、その局面に打ち進める手を持つ局面へ
+    parents: HashMap<u64, Vec<u64>>,
+    randomness: i32,
+    rng: Rng,
+}
+
+impl Book {
+    /// `x_size`x`y_size`の盤面の空の定石を生成します。
+    /// 
+    pub fn new(x_size: usize, y_size: usize) -> Book {
+        Book {
+            x_size,
+            y_size,
+            positions: HashMap::new(),
+            parents: HashMap::new(),
+            randomness: 0,
+            rng: Rng::new(0),
+        }
+    }
+
+    /// 1行に`棋譜 石差`を並べたテキストから定石を読み込みます。石差は黒から見た値で、`#`以降はコメントです。
+    /// 
+    pub fn parse(s: &str, x_size: usize, y_size: usize) -> Result<Book, ParseBookError> {
+        let mut book = Book::new(x_size, y_size);
+
+        for (index, line) in s.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (moves, score) = line.rsplit_once(char::is_whitespace).ok_or(ParseBookError::InvalidLine(line_number))?;
+            let score = score.parse::<i32>().map_err(|_| ParseBookError::InvalidScore(line_number, score.to_string()))?;
+            let points = transcript::parse(moves).map_err(|error| ParseBookError::Transcript(line_number, error))?;
+            book.add_points(&points, score).map_err(|error| ParseBookError::Transcript(line_number, error))?;
+        }
+
+        Ok(book)
+    }
+
+    /// 登録されている局面の数を返します。
+    /// 
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// 局面が1つも登録されていない場合にtrueを返します。
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// 最善手との石差が`randomness`以内の手から無作為に選ぶようにします。0の場合は常に最善手を選びます。
+    /// 
+    pub fn set_randomness(&mut self, randomness: i32) {
+        self.randomness = randomness.max(0);
+    }
+
+    /// 手を選ぶ乱数のシードを設定します。
+    /// 
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// `piece_type`の手番の盤面に登録されている手を、評価値の高い順に返します。
+    /// 
+    pub fn moves(&self, board: &Board, piece_type: PieceType) -> Vec<BookMove> {
+        self.entries(board, piece_type).into_iter().map(|(book_move, _)| book_move).collect()
+    }
+
+    /// 登録されている手から1つ選びます。登録されていない局面の場合はNoneを返します。
+    /// 
+    pub fn choose(&mut self, board: &Board, piece_type: PieceType) -> Option<BookMove> {
+        let moves = self.moves(board, piece_type);
+        let best = moves.first()?.score;
+        let candidates = moves.into_iter()
+            .filter(|book_move| book_move.score >= best - self.randomness)
+            .collect::<Vec<_>>();
+
+        Some(candidates[self.rng.gen_range(candidates.len())])
+    }
+
+    /// 棋譜と黒から見た石差を1行分登録します。
+    /// 
+    pub fn add_line(&mut self, moves: &str, score: i32) -> Result<(), TranscriptError> {
+        self.add_points(&transcript::parse(moves)?, score)
+    }
+
+    /// 対局の最初の`max_ply`手を対局の結果で登録します。
+    /// 既に登録されている棋譜の場合は、最後の手に登録した石差を結果との平均にします。
+    /// 投了で終わった対局は石差が結果を表さないため登録しません。`max_ply`はパスを数えません。
+    /// 
+    pub fn learn(&mut self, game: &Game, max_ply: usize) {
+        if game.end_reason() == Some(EndReason::Resignation) {
+            return;
+        }
+
+        let points = game.moves()
+            .iter()
+            .filter_map(|move_type| match move_type {
+                MoveType::Put(point) => Some(*point),
+                MoveType::Pass => None,
+            })
+            .take(max_ply)
+            .collect::<Vec<_>>();
+        // 実際に打たれた手なので登録は必ず成功する
+        let _ = self.add_points(&points, game.board().final_score(PieceType::Black));
+    }
+
+    // 評価値の高い順に、登録されている手と、その手で終わる棋譜に登録した石差を返す
+    fn entries(&self, board: &Board, piece_type: PieceType) -> Vec<(BookMove, Option<i32>)> {
+        if board.x_size() != self.x_size || board.y_size() != self.y_size {
+            return Vec::new();
+        }
+
+        let (key, symmetry) = board.canonical_key(piece_type);
+        let inverse = symmetry.inverse();
+        let mut entries = self.positions.get(&key)
+            .map(|edges| {
+                edges.iter()
+                    .map(|edge| {
+                        let book_move = BookMove {
+                            point: inverse.apply(&edge.point, self.x_size, self.y_size),
+                            score: edge.score,
+                            games: edge.games,
+                        };
+                        (book_move, edge.learned)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        entries.sort_by_key(|(book_move, _)| -book_move.score);
+        entries
+    }
+
+    fn add_points(&mut self, points: &[Point], score: i32) -> Result<(), TranscriptError> {
+        // 途中に打てない手があっても定石を変えないよう、先に全ての手を打ってから登録する
+        let mut game = Game::new(self.x_size, self.y_size);
+        let mut steps = Vec::new();
+        for (index, point) in points.iter().enumerate() {
+            if game.must_pass() {
+                let _ = game.pass();
+            }
+            let turn = game.turn();
+            let (key, symmetry) = game.board().canonical_key(turn);
+            game.play(*point).map_err(|error| TranscriptError::IllegalMove { ply: index + 1, error })?;
+
+            let next = next_turn(game.board(), turn).unwrap_or(turn.change());
+            let child = game.board().canonical_key(next).0;
+            steps.push((key, symmetry.apply(point, self.x_size, self.y_size), child, next == turn, turn));
+        }
+
+        let mut path = Vec::new();
+        for (index, (key, point, child, pass, turn)) in steps.into_iter().enumerate() {
+            let edges = self.positions.entry(key).or_default();
+            let edge = match edges.iter().position(|edge| edge.child == child) {
+                Some(position) => {
+                    edges[position].games += 1;
+                    &mut edges[position]
+                }
+                None => {
+                    edges.push(Edge { point, child, pass, score: 0, games: 1, learned: None, learned_games: 0 });
+                    self.parents.entry(child).or_default().push(key);
+                    edges.last_mut().expect("edge was just pushed")
+                }
+            };
+
+            if index + 1 == points.len() {
+                let score = if turn == PieceType::Black { score } else { -score };
+                edge.learned_games += 1;
+                let games = edge.learned_games as i32;
+                edge.learned = Some(edge.learned.map_or(score, |learned| (learned * (games - 1) + score) / games));
+            }
+            path.push(key);
+        }
+
+        self.propagate(&path);
+        Ok(())
+    }
+
+    // 登録した棋譜の局面を深い方から計算し直す。値の変わった局面は、合流する別の手順の局面にも伝える
+    fn propagate(&mut self, path: &[u64]) {
+        let mut forced = path.iter().copied().collect::<HashSet<_>>();
+        let mut pending = path.to_vec();
+
+        while let Some(key) = pending.pop() {
+            let before = self.value(key);
+            let scores = self.positions.get(&key)
+                .map(|edges| edges.iter().map(|edge| self.edge_score(edge)).collect::<Vec<_>>())
+                .unwrap_or_default();
+            if let Some(edges) = self.positions.get_mut(&key) {
+                for (edge, score) in edges.iter_mut().zip(scores) {
+                    edge.score = score;
+                }
+            }
+
+            if forced.remove(&key) || self.value(key) != before {
+                let parents = self.parents.get(&key).into_iter().flatten();
+                pending.extend(parents.filter(|parent| !forced.contains(parent)));
+            }
+        }
+    }
+
+    // 手番から見た局面の評価値。登録されていない局面はNone
+    fn value(&self, key: u64) -> Option<i32> {
+        self.positions.get(&key)?.iter().map(|edge| edge.score).max()
+    }
+
+    fn edge_score(&self, edge: &Edge) -> i32 {
+        match self.value(edge.child) {
+            Some(value) if edge.pass => value,
+            Some(value) => -value,
+            None => edge.learned.unwrap_or(edge.score),
+        }
+    }
+
+    fn write_lines(&self, board: &Board, piece_type: PieceType, path: &mut Vec<Point>, visited: &mut HashSet<u64>, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let black_view = |score: i32| if piece_type == PieceType::Black { score } else { -score };
+
+        for (book_move, learned) in self.entries(board, piece_type) {
+            let child = board.clone().put_piece(piece_type, book_move.point);
+            path.push(book_move.point);
+            let moves = path.iter().filter_map(Point::to_notation).collect::<String>();
+
+            let next = next_turn(&child, piece_type)
+                .map(|turn| (child.canonical_key(turn).0, turn))
+                .filter(|(key, _)| self.positions.contains_key(key));
+            match next {
+                Some((key, turn)) => {
+                    // 先が登録されている手に登録した石差は、先の棋譜とは別の行にする
+                    if let Some(learned) = learned {
+                        writeln!(f, "{} {:+}", moves, black_view(learned))?;
+                    }
+                    if visited.insert(key) {
+                        self.write_lines(&child, turn, path, visited, f)?;
+                    } else if learned.is_none() {
+                        // 別の手順で書き出した局面に合流する手は評価値で1行にする
+                        writeln!(f, "{} {:+}", moves, black_view(book_move.score))?;
+                    }
+                }
+                None => writeln!(f, "{} {:+}", moves, black_view(learned.unwrap_or(book_move.score)))?,
+            }
+
+            path.pop();
+        }
+        Ok(())
+    }
+}
+
+/// `Book::parse`で読み込める形式で書き出します。
+/// 
+impl fmt::Display for Book {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let board = Board::new(self.x_size, self.y_size);
+        self.write_lines(&board, PieceType::Black, &mut Vec::new(), &mut HashSet::new(), f)
+    }
+}
+
+// `piece_type`が打った後の手番を返す。終局した場合はNone
+fn next_turn(board: &Board, piece_type: PieceType) -> Option<PieceType> {
+    if board.is_end() {
+        None
+    } else if board.is_skip(piece_type.change()) {
+        Some(piece_type)
+    } else {
+        Some(piece_type.change())
+    }
+}
+
+/// 定石を読み込めなかった理由。行番号は1始まりです。
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseBookError {
+    /// `棋譜 石差`の形式ではない
+    InvalidLine(usize),
+    /// 石差が整数ではない
+    InvalidScore(usize, String),
+    /// 棋譜が不正
+    Transcript(usize, TranscriptError),
+}
+
+impl fmt::Display for ParseBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBookError::InvalidLine(line) => write!(f, "{}行目: `棋譜 石差`の形式ではありません", line),
+            ParseBookError::InvalidScore(line, score) => write!(f, "{}行目: 石差が不正です : {}", line, score),
+            ParseBookError::Transcript(line, error) => write!(f, "{}行目: {}", line, error),
+        }
+    }
+}
+
+impl Error for ParseBookError {}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOK: &str = "\
+# 縦取り
+f5d6 +2
+f5f6 -4
+f5f4 +8
+";
+
+    #[test]
+    fn test_parse() {
+        let book = Book::parse(BOOK, 8, 8).unwrap();
+        assert_eq!(2, book.len());
+
+        // 白は黒から見て-4になるf6を選ぶため、黒のf5の評価値は-4になる
+        let board = Board::new(8, 8);
+        let moves = book.moves(&board, PieceType::Black);
+        assert_eq!(1, moves.len());
+        assert_eq!(-4, moves[0].score);
+        assert!(board.can_put_piece(PieceType::Black, &moves[0].point));
+
+        let board = board.put_piece(PieceType::Black, Point::new(6, 5));
+        let moves = book.moves(&board, PieceType::White);
+        assert_eq!(vec![4, -2, -8], moves.iter().map(|book_move| book_move.score).collect::<Vec<_>>());
+        assert_eq!(Point::new(6, 6), moves[0].point);
+
+        assert_eq!(Err(ParseBookError::InvalidLine(1)), Book::parse("f5d6", 8, 8).map(|_| ()));
+        assert_eq!(Err(ParseBookError::InvalidScore(2, "x".to_string())), Book::parse("f5 0\nf5d6 x", 8, 8).map(|_| ()));
+        assert!(matches!(Book::parse("f5f5 0", 8, 8), Err(ParseBookError::Transcript(1, TranscriptError::IllegalMove { ply: 2, .. }))));
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let book = Book::parse(BOOK, 8, 8).unwrap();
+
+        // d3はf5を対角線で反転した手なので、f6に対応するd3からの手はf4を反転したe3ではなくc3
+        let board = Board::new(8, 8).put_piece(PieceType::Black, Point::new(4, 3));
+        let moves = book.moves(&board, PieceType::White);
+        assert_eq!(Point::new(3, 3), moves[0].point);
+        assert_eq!(4, moves[0].score);
+    }
+
+    #[test]
+    fn test_choose() {
+        let mut book = Book::parse(BOOK, 8, 8).unwrap();
+        let board = Board::new(8, 8).put_piece(PieceType::Black, Point::new(6, 5));
+
+        for seed in 0..10 {
+            book.set_seed(seed);
+            assert_eq!(Point::new(6, 6), book.choose(&board, PieceType::White).unwrap().point);
+        }
+
+        // 最善手から6石差以内ならd6も選ばれる
+        book.set_randomness(6);
+        let chosen = (0..20)
+            .map(|seed| {
+                book.set_seed(seed);
+                book.choose(&board, PieceType::White).unwrap().point
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(HashSet::from([Point::new(6, 6), Point::new(4, 6)]), chosen);
+
+        // 登録されている手の先の局面は登録されていない
+        let board = board.put_piece(PieceType::White, Point::new(6, 4));
+        assert_eq!(None, book.choose(&board, PieceType::Black));
+    }
+
+    #[test]
+    fn test_extend_line() {
+        let mut book = Book::parse("f5d6 +2", 8, 8).unwrap();
+        let board = Board::new(8, 8).put_piece(PieceType::Black, Point::new(6, 5));
+
+        // 棋譜を延ばすと評価値は先の局面から伝わり、短い棋譜に登録した石差は別に残る
+        book.add_line("f5d6c3", 6).unwrap();
+        book.add_line("f5d6", 4).unwrap();
+        assert_eq!(-6, book.moves(&board, PieceType::White)[0].score);
+        let written = book.to_string();
+        assert_eq!("f5d6 +3\nf5d6c3 +6\n", written);
+        assert_eq!(written, Book::parse(&written, 8, 8).unwrap().to_string());
+
+        // 別の手順で合流する局面の評価値も更新される
+        let mut book = Book::parse("f5d6c4d3c3 +2", 8, 8).unwrap();
+        assert!(*transcript::replay("f5d6c4d3c3", 8, 8).unwrap().board() == *transcript::replay("f5d6c3d3c4", 8, 8).unwrap().board());
+        book.add_line("f5d6c3d3c4f4", 10).unwrap();
+        let board = transcript::replay("f5d6c4d3", 8, 8).unwrap();
+        assert_eq!(10, book.moves(board.board(), PieceType::Black)[0].score);
+        assert_eq!(10, book.moves(&Board::new(8, 8), PieceType::Black)[0].score);
+    }
+
+    #[test]
+    fn test_learn_and_write() {
+        let mut book = Book::new(4, 4);
+        assert!(book.is_empty());
+
+        // 両者打てなくなり白が8石差で勝つ対局
+        let mut game = Game::new(4, 4);
+        for point in [(2, 1), (1, 1), (1, 2), (3, 1), (4, 2), (1, 3), (2, 4), (4, 3), (4, 4)] {
+            game.play(Point::new(point.0, point.1)).unwrap();
+        }
+        book.learn(&game, 2);
+        assert_eq!(-8, book.moves(&Board::new(4, 4), PieceType::Black)[0].score);
+
+        // 同じ棋譜は平均になる
+        book.add_line("b1a1", 0).unwrap();
+        assert_eq!(-4, book.moves(&Board::new(4, 4), PieceType::Black)[0].score);
+        assert_eq!(2, book.moves(&Board::new(4, 4), PieceType::Black)[0].games);
+
+        let book = Book::parse(BOOK, 8, 8).unwrap();
+        let written = book.to_string();
+        assert_eq!(3, written.lines().count());
+        assert_eq!(written, Book::parse(&written, 8, 8).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_learn_resigned() {
+        // 投了した対局は盤面の石差に関わらず登録しない
+        let mut book = Book::new(4, 4);
+        let mut game = Game::new(4, 4);
+        game.play(Point::new(2, 1)).unwrap();
+        game.resign().unwrap();
+        book.learn(&game, 2);
+        assert!(book.is_empty());
+    }
+}
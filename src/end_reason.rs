@@ -0,0 +1,27 @@
+use std::fmt;
+
+/// ゲームが終了した理由
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EndReason {
+    /// 盤面が全て埋まった
+    BoardFull,
+    /// どちらかの駒が無くなった
+    WipeOut,
+    /// 両者とも打てる場所が無い
+    MutualPass,
+    /// 投了した
+    Resignation,
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            EndReason::BoardFull => "盤面が全て埋まりました",
+            EndReason::WipeOut => "どちらかの駒が無くなりました",
+            EndReason::MutualPass => "両者とも打てる場所がありません",
+            EndReason::Resignation => "投了しました",
+        };
+        write!(f, "{}", str)
+    }
+}
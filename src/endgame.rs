@@ -0,0 +1,402 @@
+//! 終盤の完全読み
+//! 
+//! 残りの空きマスを最後まで読み切り、最善を尽くした時の石差を求めます。
+//! 8x8の盤面はビットボードのまま読み、それ以外の大きさは`Board`の手を使って読みます。
+//! 
+
+use crate::bitboard::{self, Bitboard};
+use crate::board::Board;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+
+// 速さ優先(相手の打てる場所が少ない順)で並べ替える空きマスの数の下限
+const FASTEST_FIRST_EMPTIES: u32 = 7;
+
+// 4x4の区画ごとのマスク。空きマスが奇数の区画を先に読む(偶数理論)
+const QUADRANTS: [u64; 4] = [
+    0x0000_0000_0f0f_0f0f,
+    0x0000_0000_f0f0_f0f0,
+    0x0f0f_0f0f_0000_0000,
+    0xf0f0_f0f0_0000_0000,
+];
+
+/// 読みの種類
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolveMode {
+    /// 石差まで求める
+    Exact,
+    /// 勝ち・負け・引き分けだけを求める(速い)
+    WinLossDraw,
+}
+
+/// # EndgameResult 構造体
+/// 
+/// 完全読みの結果
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EndgameResult {
+    /// 最善手。打てる場所が無くパスする場合はNone
+    pub best_move: Option<Point>,
+    /// 手番から見た石差。`SolveMode::WinLossDraw`の場合は勝ちが1、負けが-1、引き分けが0
+    pub score: i32,
+    /// 読んだノード数
+    pub nodes: u64,
+}
+
+/// # EndgameSolver 構造体
+/// 
+/// 終盤を最後まで読み切る構造体
+/// 
+#[derive(Default)]
+pub struct EndgameSolver {
+    nodes: u64,
+}
+
+impl EndgameSolver {
+    pub fn new() -> EndgameSolver {
+        EndgameSolver {
+            nodes: 0,
+        }
+    }
+
+    /// `piece_type`の手番で最後まで読み、石差と最善手を返します。
+    /// 石差は空きマスを勝った側に加えて数えます。
+    /// 
+    pub fn solve(&mut self, board: &Board, piece_type: PieceType) -> EndgameResult {
+        self.solve_with_mode(board, piece_type, SolveMode::Exact)
+    }
+
+    /// `piece_type`の手番で最後まで読み、勝ち負けと最善手だけを返します。
+    /// 
+    pub fn solve_wld(&mut self, board: &Board, piece_type: PieceType) -> EndgameResult {
+        self.solve_with_mode(board, piece_type, SolveMode::WinLossDraw)
+    }
+
+    /// 読みの種類を指定して最後まで読みます。
+    /// 
+    pub fn solve_with_mode(&mut self, board: &Board, piece_type: PieceType, mode: SolveMode) -> EndgameResult {
+        self.nodes = 0;
+
+        let limit = (board.x_size() * board.y_size()) as i32 + 1;
+        let (alpha, beta) = match mode {
+            SolveMode::Exact => (-limit, limit),
+            SolveMode::WinLossDraw => (-1, 1),
+        };
+
+        let (best_move, score) = match board.bitboard() {
+            Some(bitboard) => {
+                let player = bitboard.pieces(piece_type);
+                let opponent = bitboard.pieces(piece_type.change());
+                self.root_bits(player, opponent, alpha, beta)
+            }
+            None => self.root_board(&mut board.clone(), piece_type, alpha, beta),
+        };
+
+        EndgameResult {
+            best_move,
+            score: match mode {
+                SolveMode::Exact => score,
+                SolveMode::WinLossDraw => score.signum(),
+            },
+            nodes: self.nodes,
+        }
+    }
+
+    fn root_bits(&mut self, player: u64, opponent: u64, mut alpha: i32, beta: i32) -> (Option<Point>, i32) {
+        let moves = bitboard::legal_moves(player, opponent);
+        if moves == 0 {
+            return (None, self.solve_bits(player, opponent, alpha, beta, false));
+        }
+
+        let mut best = (None, -i32::MAX);
+        for index in order_bits(player, opponent, moves) {
+            let flips = bitboard::flips(player, opponent, index);
+            let score = -self.solve_bits(opponent & !flips, player | flips | (1 << index), -beta, -alpha, false);
+            if score > best.1 {
+                best = (Some(Bitboard::point(index)), score);
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    fn solve_bits(&mut self, player: u64, opponent: u64, mut alpha: i32, beta: i32, passed: bool) -> i32 {
+        self.nodes += 1;
+
+        let empties = !(player | opponent);
+        match empties.count_ones() {
+            0 => return disc_difference(player, opponent),
+            1 => return self.solve_last(player, opponent, empties.trailing_zeros()),
+            2..=4 => return self.solve_few(player, opponent, alpha, beta, passed),
+            _ => (),
+        }
+
+        let moves = bitboard::legal_moves(player, opponent);
+        if moves == 0 {
+            if passed {
+                return final_difference(player, opponent);
+            }
+            return -self.solve_bits(opponent, player, -beta, -alpha, true);
+        }
+
+        let mut best = -i32::MAX;
+        for index in order_bits(player, opponent, moves) {
+            let flips = bitboard::flips(player, opponent, index);
+            let score = -self.solve_bits(opponent & !flips, player | flips | (1 << index), -beta, -alpha, false);
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    // 空きマスが4つ以下の場合は着手生成をせず、偶数理論の順に空きマスを直接試す
+    fn solve_few(&mut self, player: u64, opponent: u64, mut alpha: i32, beta: i32, passed: bool) -> i32 {
+        let empties = !(player | opponent);
+        let odd = odd_quadrants(empties);
+        let squares = Bitboard::indexes(empties & odd).chain(Bitboard::indexes(empties & !odd));
+
+        let mut best = -i32::MAX;
+        for index in squares {
+            let flips = bitboard::flips(player, opponent, index);
+            if flips == 0 {
+                continue;
+            }
+
+            let score = -self.solve_bits(opponent & !flips, player | flips | (1 << index), -beta, -alpha, false);
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        if best == -i32::MAX {
+            if passed {
+                return final_difference(player, opponent);
+            }
+            return -self.solve_bits(opponent, player, -beta, -alpha, true);
+        }
+
+        best
+    }
+
+    // 最後の1マスは打てる側の返る駒の数だけで石差が決まる
+    fn solve_last(&mut self, player: u64, opponent: u64, index: u32) -> i32 {
+        let difference = disc_difference(player, opponent);
+
+        let flips = bitboard::flips(player, opponent, index).count_ones() as i32;
+        if flips > 0 {
+            return difference + 2 * flips + 1;
+        }
+
+        let flips = bitboard::flips(opponent, player, index).count_ones() as i32;
+        if flips > 0 {
+            return difference - 2 * flips - 1;
+        }
+
+        // どちらも打てなければ空きマスは勝った側に加える
+        difference + difference.signum()
+    }
+
+    fn root_board(&mut self, board: &mut Board, piece_type: PieceType, mut alpha: i32, beta: i32) -> (Option<Point>, i32) {
+        let moves = board.legal_moves(piece_type);
+        if moves.is_empty() {
+            return (None, self.solve_board(board, piece_type, alpha, beta));
+        }
+
+        let mut best = (None, -i32::MAX);
+        for point in order_points(board, piece_type, moves) {
+            let undo = board.make_move(piece_type, point);
+            let score = -self.solve_board(board, piece_type.change(), -beta, -alpha);
+            board.unmake_move(undo);
+
+            if score > best.1 {
+                best = (Some(point), score);
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    fn solve_board(&mut self, board: &mut Board, piece_type: PieceType, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+
+        let moves = board.legal_moves(piece_type);
+        if moves.is_empty() {
+            if board.is_skip(piece_type.change()) {
+                return board.final_score(piece_type);
+            }
+            return -self.solve_board(board, piece_type.change(), -beta, -alpha);
+        }
+
+        let mut best = -i32::MAX;
+        for point in order_points(board, piece_type, moves) {
+            let undo = board.make_move(piece_type, point);
+            let score = -self.solve_board(board, piece_type.change(), -beta, -alpha);
+            board.unmake_move(undo);
+
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+}
+
+fn disc_difference(player: u64, opponent: u64) -> i32 {
+    player.count_ones() as i32 - opponent.count_ones() as i32
+}
+
+// 終局時の石差。空きマスは勝った側に加える
+fn final_difference(player: u64, opponent: u64) -> i32 {
+    let difference = disc_difference(player, opponent);
+    let empties = (!(player | opponent)).count_ones() as i32;
+    difference + difference.signum() * empties
+}
+
+fn odd_quadrants(empties: u64) -> u64 {
+    QUADRANTS.iter()
+        .filter(|&&quadrant| (empties & quadrant).count_ones() % 2 == 1)
+        .fold(0, |acc, quadrant| acc | quadrant)
+}
+
+// 空きマスが多い間は相手の打てる場所が少ない順(速さ優先)、少なくなったら偶数理論の順に並べる
+fn order_bits(player: u64, opponent: u64, moves: u64) -> Vec<u32> {
+    let empties = !(player | opponent);
+    let odd = odd_quadrants(empties);
+
+    let mut ordered = Bitboard::indexes(moves)
+        .map(|index| {
+            let parity = if odd & (1 << index) != 0 { 0 } else { 1 };
+            let mobility = if empties.count_ones() > FASTEST_FIRST_EMPTIES {
+                let flips = bitboard::flips(player, opponent, index);
+                bitboard::legal_moves(opponent & !flips, player | flips | (1 << index)).count_ones()
+            } else {
+                0
+            };
+            (index, mobility, parity)
+        })
+        .collect::<Vec<_>>();
+    ordered.sort_by_key(|&(_, mobility, parity)| (mobility, parity));
+
+    ordered.into_iter().map(|(index, _, _)| index).collect()
+}
+
+fn order_points(board: &mut Board, piece_type: PieceType, moves: Vec<Point>) -> Vec<Point> {
+    let mut ordered = moves.into_iter()
+        .map(|point| {
+            let undo = board.make_move(piece_type, point);
+            let mobility = board.legal_move_count(piece_type.change());
+            board.unmake_move(undo);
+            (point, mobility)
+        })
+        .collect::<Vec<_>>();
+    ordered.sort_by_key(|&(_, mobility)| mobility);
+
+    ordered.into_iter().map(|(point, _)| point).collect()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_player::RandomPlayer;
+    use crate::player::Player;
+    use crate::search::{Search, SCORE_WIN};
+
+    // ランダムに打ち進めて空きマスが`empties`個の局面を作る
+    fn random_position(x_size: usize, y_size: usize, empties: usize, seed: u64) -> Option<(Board, PieceType)> {
+        let mut board = Board::new(x_size, y_size);
+        let mut piece_type = PieceType::Black;
+        let mut player = RandomPlayer::new(seed);
+
+        while board.empty_count() > empties {
+            if board.is_end() {
+                return None;
+            }
+            if let Some(point) = player.choose_move(&board, piece_type) {
+                board = board.put_piece(piece_type, point);
+            }
+            piece_type = piece_type.change();
+        }
+
+        Some((board, piece_type))
+    }
+
+    // 評価関数を使わず最後まで読んだαβ探索の結果
+    fn brute_force(board: &Board, piece_type: PieceType) -> i32 {
+        let mut search = Search::new(64);
+        search.set_endgame_empties(0);
+        let score = search.search(board, piece_type).score;
+        score - score.signum() * SCORE_WIN
+    }
+
+    #[test]
+    fn test_solve() {
+        for seed in 0..20 {
+            let Some((board, piece_type)) = random_position(8, 8, 9, seed) else { continue };
+
+            let mut solver = EndgameSolver::new();
+            let result = solver.solve(&board, piece_type);
+            assert_eq!(brute_force(&board, piece_type), result.score);
+            assert!(result.nodes > 0);
+
+            // 最善手を打った後は相手から見て逆の石差になる
+            if let Some(point) = result.best_move {
+                let next = board.clone().put_piece(piece_type, point);
+                assert_eq!(-result.score, solver.solve(&next, piece_type.change()).score);
+            }
+
+            assert_eq!(result.score.signum(), solver.solve_wld(&board, piece_type).score);
+        }
+    }
+
+    #[test]
+    fn test_solve_general() {
+        for seed in 0..10 {
+            let Some((board, piece_type)) = random_position(6, 6, 8, seed) else { continue };
+
+            let mut solver = EndgameSolver::new();
+            let result = solver.solve(&board, piece_type);
+            assert_eq!(brute_force(&board, piece_type), result.score);
+            assert_eq!(result.score.signum(), solver.solve_wld(&board, piece_type).score);
+        }
+    }
+
+    #[test]
+    fn test_solve_last() {
+        //  |0|1|2|3|4|5|6|7|8|9|
+        // 1|×|●|●|●|●|●|●|●|●|×|
+        // ...                     すべて黒
+        // 8|×|●|●|●|●|●|●|○| |×|
+        let position = "X".repeat(62) + "O- X";
+        let (board, piece_type) = Board::from_position_str(&position, 8, 8).unwrap();
+
+        let result = EndgameSolver::new().solve(&board, piece_type);
+        assert_eq!(Some(Point::new(8, 8)), result.best_move);
+        assert_eq!(64, result.score);
+    }
+}
@@ -0,0 +1,11 @@
+use crate::board::Board;
+use crate::piece_type::PieceType;
+
+/// 盤面の静的評価
+/// 
+pub trait Evaluator {
+    /// `piece_type`の手番から見た盤面の評価値を返します。
+    /// 値が大きいほど`piece_type`が有利です。
+    /// 
+    fn evaluate(&self, board: &Board, piece_type: PieceType) -> i32;
+}
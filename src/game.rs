@@ -0,0 +1,263 @@
+use crate::board::Board;
+use crate::end_reason::EndReason;
+use crate::move_error::MoveError;
+use crate::move_type::MoveType;
+use crate::move_undo::MoveUndo;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+
+/// # Game 構造体
+/// 
+/// 盤面と手番、パスを含めた棋譜を管理する構造体
+/// 
+#[derive(Clone)]
+pub struct Game {
+    board: Board,
+    turn: PieceType,
+    moves: Vec<MoveType>,
+    undos: Vec<Option<MoveUndo>>,
+    redo_moves: Vec<MoveType>,
+    resigned: Option<PieceType>,
+}
+
+impl Game {
+    /// 新しくゲームを開始します。先手は黒です。
+    /// 
+    pub fn new(x_size: usize, y_size: usize) -> Game {
+        Game::from_board(Board::new(x_size, y_size), PieceType::Black)
+    }
+
+    /// 指定した盤面と手番からゲームを開始します。
+    /// 
+    pub fn from_board(board: Board, turn: PieceType) -> Game {
+        Game {
+            board,
+            turn,
+            moves: Vec::new(),
+            undos: Vec::new(),
+            redo_moves: Vec::new(),
+            resigned: None,
+        }
+    }
+
+    /// 現在の盤面を返します。
+    /// 
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// 手番の駒の種類を返します。
+    /// 
+    pub fn turn(&self) -> PieceType {
+        self.turn
+    }
+
+    /// パスを含めたこれまでの手を返します。
+    /// 
+    pub fn moves(&self) -> &[MoveType] {
+        &self.moves
+    }
+
+    /// ゲームが終了していたらtrueを返します。
+    /// 
+    pub fn is_end(&self) -> bool {
+        self.end_reason().is_some()
+    }
+
+    /// ゲームが終了した理由を返します。
+    /// ゲームが終了していない場合はNoneが返却されます。
+    /// 
+    pub fn end_reason(&self) -> Option<EndReason> {
+        if self.resigned.is_some() {
+            Some(EndReason::Resignation)
+        } else {
+            self.board.end_reason()
+        }
+    }
+
+    /// ゲームの勝者を返却します。
+    /// 投了した場合は相手の勝ち、それ以外は駒の多い方の勝ちです。
+    /// 
+    pub fn get_winner(&self) -> Option<PieceType> {
+        match self.resigned {
+            Some(piece_type) => Some(piece_type.change()),
+            None => self.board.get_winner(),
+        }
+    }
+
+    /// 手番の側が投了します。
+    /// 
+    pub fn resign(&mut self) -> Result<(), MoveError> {
+        if self.is_end() {
+            return Err(MoveError::GameOver);
+        }
+        self.resigned = Some(self.turn);
+        Ok(())
+    }
+
+    /// 手番の打てる場所が無く、パスしなければならない場合にtrueを返します。
+    /// 
+    pub fn must_pass(&self) -> bool {
+        !self.is_end() && self.board.is_skip(self.turn)
+    }
+
+    /// 手番の駒を置き、手番を交代します。
+    /// 
+    pub fn play(&mut self, point: Point) -> Result<(), MoveError> {
+        self.apply(MoveType::Put(point))?;
+        self.redo_moves.clear();
+        Ok(())
+    }
+
+    /// 手番をパスします。打てる場所がある場合はパスできません。
+    /// 
+    pub fn pass(&mut self) -> Result<(), MoveError> {
+        self.apply(MoveType::Pass)?;
+        self.redo_moves.clear();
+        Ok(())
+    }
+
+    /// 最後の一手を取り消します。取り消した手が返却されます。
+    /// 投了している場合は投了だけを取り消し、盤面と棋譜は変えずにNoneを返却します。
+    /// 
+    pub fn undo(&mut self) -> Option<MoveType> {
+        if self.resigned.take().is_some() {
+            return None;
+        }
+
+        let move_type = self.moves.pop()?;
+        if let Some(Some(undo)) = self.undos.pop() {
+            self.board.unmake_move(undo);
+        }
+        self.turn = self.turn.change();
+        self.redo_moves.push(move_type);
+        Some(move_type)
+    }
+
+    /// `undo`で取り消した手をやり直します。やり直した手が返却されます。
+    /// 
+    pub fn redo(&mut self) -> Option<MoveType> {
+        let move_type = self.redo_moves.pop()?;
+        match self.apply(move_type) {
+            Ok(()) => Some(move_type),
+            Err(_) => {
+                self.redo_moves.clear();
+                None
+            }
+        }
+    }
+
+    fn apply(&mut self, move_type: MoveType) -> Result<(), MoveError> {
+        if self.is_end() {
+            return Err(MoveError::GameOver);
+        }
+
+        let undo = match move_type {
+            MoveType::Put(point) => {
+                self.board.check_move(self.turn, &point)?;
+                Some(self.board.make_move(self.turn, point))
+            }
+            MoveType::Pass => {
+                if !self.board.is_skip(self.turn) {
+                    return Err(MoveError::CannotPass);
+                }
+                None
+            }
+        };
+
+        self.moves.push(move_type);
+        self.undos.push(undo);
+        self.turn = self.turn.change();
+        Ok(())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_and_undo() {
+        let mut game = Game::new(8, 8);
+        let original = game.board().clone();
+
+        assert_eq!(Ok(()), game.play(Point::new(4, 3)));
+        assert_eq!(PieceType::White, game.turn());
+        assert_eq!(Err(MoveError::Occupied(Point::new(4, 3))), game.play(Point::new(4, 3)));
+        assert_eq!(Err(MoveError::CannotPass), game.pass());
+
+        assert_eq!(Some(MoveType::Put(Point::new(4, 3))), game.undo());
+        assert_eq!(PieceType::Black, game.turn());
+        assert!(*game.board() == original);
+        assert_eq!(None, game.undo());
+
+        assert_eq!(Some(MoveType::Put(Point::new(4, 3))), game.redo());
+        assert_eq!(PieceType::White, game.turn());
+        assert_eq!(None, game.redo());
+    }
+
+    #[test]
+    fn test_pass() {
+        let mut game = Game::new(4, 4);
+
+        //  |0|1|2|3|4|5|
+        // 0|×|×|×|×|×|×|
+        // 1|×|○|○|○| |×|
+        // 2|×| |●|●| |×|
+        // 3|×| |●|●|●|×|
+        // 4|×| | | | |×|
+        // 5|×|×|×|×|×|×|
+        for (x, y) in [(2, 1), (3, 1), (4, 3), (1, 1)] {
+            assert_eq!(Ok(()), game.play(Point::new(x, y)));
+        }
+
+        // 黒は打てないのでパスする
+        assert!(game.must_pass());
+        assert_eq!(Ok(()), game.pass());
+        assert_eq!(PieceType::White, game.turn());
+        assert_eq!(5, game.moves().len());
+        assert_eq!(MoveType::Pass, game.moves()[4]);
+
+        assert_eq!(Some(MoveType::Pass), game.undo());
+        assert_eq!(PieceType::Black, game.turn());
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut game = Game::new(8, 8);
+
+        assert_eq!(Ok(()), game.play(Point::new(4, 3)));
+        assert_eq!(Ok(()), game.resign());
+        assert_eq!(Some(EndReason::Resignation), game.end_reason());
+        assert_eq!(Some(PieceType::Black), game.get_winner());
+        assert_eq!(Err(MoveError::GameOver), game.play(Point::new(3, 3)));
+
+        // 最初の取り消しは投了だけを取り消し、盤面と棋譜はそのまま
+        let board = game.board().clone();
+        assert_eq!(None, game.undo());
+        assert_eq!(None, game.end_reason());
+        assert!(*game.board() == board);
+        assert_eq!(1, game.moves().len());
+        assert_eq!(PieceType::White, game.turn());
+
+        // 次の取り消しで手を取り消す
+        assert_eq!(Some(MoveType::Put(Point::new(4, 3))), game.undo());
+        assert_eq!(PieceType::Black, game.turn());
+    }
+
+    #[test]
+    fn test_mutual_pass() {
+        let mut game = Game::new(4, 4);
+
+        for (x, y) in [(2, 1), (1, 1), (1, 2), (3, 1), (4, 2), (1, 3), (2, 4), (4, 3), (4, 4)] {
+            assert_eq!(Ok(()), game.play(Point::new(x, y)));
+        }
+
+        // 両者とも打てないのでパスせずに終了する
+        assert_eq!(Some(EndReason::MutualPass), game.end_reason());
+        assert!(!game.must_pass());
+        assert_eq!(Err(MoveError::GameOver), game.pass());
+    }
+}
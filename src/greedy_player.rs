@@ -0,0 +1,76 @@
+use crate::board::Board;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::point::Point;
+
+/// # GreedyPlayer 構造体
+/// 
+/// その手で返せる駒が最も多い場所を選ぶプレイヤー。
+/// 同じ数の場所が複数ある場合は盤面の左上に近い方を選びます。
+/// 
+pub struct GreedyPlayer;
+
+impl GreedyPlayer {
+    pub fn new() -> GreedyPlayer {
+        GreedyPlayer
+    }
+}
+
+impl Default for GreedyPlayer {
+    fn default() -> Self {
+        GreedyPlayer::new()
+    }
+}
+
+impl Player for GreedyPlayer {
+    fn choose_move(&mut self, board: &Board, piece_type: PieceType) -> Option<Point> {
+        let mut result: Option<(Point, usize)> = None;
+
+        for point in board.legal_moves(piece_type) {
+            let count = board.flips(piece_type, &point).len();
+            if result.is_none_or(|(_, best)| count > best) {
+                result = Some((point, count));
+            }
+        }
+
+        result.map(|(point, _)| point)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_move() {
+        //  |0|1|2|3|4|5|6|7|8|9|
+        // 0|×|×|×|×|×|×|×|×|×|×|
+        // 1|×| | | |●|○| | | |×|
+        // 2|×| | | | | | | | |×|
+        // 3|×| |●|○|○| | | | |×|
+        // 4|×| | | | | | | | |×|
+        // 5|×| | | | | | | | |×|
+        // 6|×| | | | | | | | |×|
+        // 7|×| | | | | | | | |×|
+        // 8|×| | | | | | | | |×|
+        // 9|×|×|×|×|×|×|×|×|×|×|
+        let position = "---XO---".to_string() + "--------" + "-XOO----" + &"-".repeat(40) + " X";
+        let (board, turn) = Board::from_position_str(&position, 8, 8).ok().unwrap();
+
+        // (6,1) は1枚、(5,3) は2枚返せる
+        assert_eq!(Some(Point::new(5, 3)), GreedyPlayer::new().choose_move(&board, turn));
+    }
+
+    #[test]
+    fn test_choose_move_pass() {
+        let board = Board::new(4, 4)
+            .put_piece(PieceType::Black, Point::new(2, 1))
+            .put_piece(PieceType::White, Point::new(3, 1))
+            .put_piece(PieceType::Black, Point::new(4, 3))
+            .put_piece(PieceType::White, Point::new(1, 1));
+
+        assert_eq!(None, GreedyPlayer::new().choose_move(&board, PieceType::Black));
+    }
+}
@@ -32,4 +32,32 @@ pub mod board;
 pub mod piece_type;
 mod tile_type;
 pub mod point;
+pub mod move_undo;
+pub mod move_error;
+pub mod move_type;
+pub mod game;
+pub mod end_reason;
+pub mod transcript;
+pub mod player;
+pub mod random_player;
+pub mod greedy_player;
+pub mod search;
+pub mod evaluator;
+pub mod positional_evaluator;
+pub mod endgame;
+pub mod mcts;
+pub mod zobrist;
+pub mod symmetry;
+pub mod transposition_table;
+pub mod time_control;
+pub mod book;
+pub mod pattern_evaluator;
+pub mod pattern_trainer;
+pub mod self_play;
+pub mod arena;
+pub mod tournament;
+pub mod perft;
+pub mod score_type;
+mod rng;
 mod tile;
+mod bitboard;
@@ -0,0 +1,335 @@
+//! モンテカルロ木探索(UCT)
+//! 
+//! 評価関数を使わずランダムな終局までのプレイアウトの勝率で手を選ぶため、
+//! 重みの決まっていない大きさの盤面でも使えます。
+//! 
+
+use crate::board::Board;
+use crate::move_type::MoveType;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::point::Point;
+use crate::rng::Rng;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+/// 一手を選ぶために使う探索量
+/// 
+/// 打てる場所があれば、どちらの場合も少なくとも1回はプレイアウトしてから手を選びます。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Budget {
+    /// プレイアウトの回数
+    Iterations(u32),
+    /// 考える時間
+    Time(Duration),
+}
+
+struct Node {
+    move_type: Option<MoveType>,
+    turn: PieceType,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<MoveType>,
+    visits: u32,
+    // この節点に至る手を打った側から見た勝ち数(引き分けは0.5)
+    wins: f64,
+}
+
+struct Tree {
+    board: Board,
+    nodes: Vec<Node>,
+}
+
+/// # MctsPlayer 構造体
+/// 
+/// モンテカルロ木探索で手を選ぶプレイヤー
+/// 
+/// 前回の探索木のうち実際に進んだ局面以下を次の手番で再利用します。
+/// 同じシードと`Budget::Iterations`なら同じ手を選びます。
+/// 
+pub struct MctsPlayer {
+    budget: Budget,
+    exploration: f64,
+    rng: Rng,
+    tree: Option<Tree>,
+}
+
+impl MctsPlayer {
+    pub fn new(budget: Budget, seed: u64) -> MctsPlayer {
+        MctsPlayer {
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            rng: Rng::new(seed),
+            tree: None,
+        }
+    }
+
+    /// UCTの探索の強さを表す定数を設定します。既定値は√2です。
+    /// 
+    pub fn set_exploration(&mut self, exploration: f64) {
+        self.exploration = exploration;
+    }
+
+    /// 現在の探索木の根の訪問回数を返します。
+    /// 
+    pub fn root_visits(&self) -> u32 {
+        self.tree.as_ref().map_or(0, |tree| tree.nodes[0].visits)
+    }
+
+    fn iterate(&mut self, tree: &mut Tree) {
+        let mut board = tree.board.clone();
+        let mut index = 0;
+
+        // 選択: 全ての手を試した節点はUCTの値が最大の子をたどる
+        while tree.nodes[index].untried.is_empty() && !tree.nodes[index].children.is_empty() {
+            index = self.select_child(tree, index);
+            apply(&mut board, tree.nodes[index].turn.change(), tree.nodes[index].move_type);
+        }
+
+        // 展開: まだ試していない手を1つ選んで子を追加する
+        if !tree.nodes[index].untried.is_empty() {
+            let untried = &mut tree.nodes[index].untried;
+            let move_type = untried.swap_remove(self.rng.gen_range(untried.len()));
+            let turn = tree.nodes[index].turn;
+            apply(&mut board, turn, Some(move_type));
+
+            let child = Node::new(&board, turn.change(), Some(move_type), Some(index));
+            tree.nodes.push(child);
+            let child_index = tree.nodes.len() - 1;
+            tree.nodes[index].children.push(child_index);
+            index = child_index;
+        }
+
+        // シミュレーション: 終局までランダムに打つ
+        let winner = self.playout(&mut board, tree.nodes[index].turn);
+
+        // 逆伝播
+        let mut current = Some(index);
+        while let Some(i) = current {
+            let node = &mut tree.nodes[i];
+            node.visits += 1;
+            node.wins += match winner {
+                Some(piece_type) if piece_type == node.turn.change() => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    fn select_child(&self, tree: &Tree, index: usize) -> usize {
+        let log_visits = (tree.nodes[index].visits as f64).ln();
+        let uct = |child: usize| {
+            let node = &tree.nodes[child];
+            let visits = node.visits as f64;
+            node.wins / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+
+        tree.nodes[index].children.iter()
+            .copied()
+            .max_by(|&a, &b| uct(a).partial_cmp(&uct(b)).unwrap_or(Ordering::Equal))
+            .unwrap_or(index)
+    }
+
+    fn playout(&mut self, board: &mut Board, mut piece_type: PieceType) -> Option<PieceType> {
+        loop {
+            let moves = board.legal_moves(piece_type);
+            if moves.is_empty() {
+                if board.is_skip(piece_type.change()) {
+                    return board.get_winner();
+                }
+            } else {
+                board.make_move(piece_type, moves[self.rng.gen_range(moves.len())]);
+            }
+            piece_type = piece_type.change();
+        }
+    }
+}
+
+impl Player for MctsPlayer {
+    fn choose_move(&mut self, board: &Board, piece_type: PieceType) -> Option<Point> {
+        if board.is_skip(piece_type) {
+            return None;
+        }
+
+        let mut tree = self.tree.take()
+            .and_then(|tree| tree.reuse(board, piece_type))
+            .unwrap_or_else(|| Tree::new(board.clone(), piece_type));
+
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            // 訪問した子が無いと手を選べないため、探索量に関わらず1回は探索する
+            let done = iterations > 0 && match self.budget {
+                Budget::Iterations(limit) => iterations >= limit,
+                Budget::Time(limit) => start.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+            self.iterate(&mut tree);
+            iterations += 1;
+        }
+
+        // 最も多く訪問した手を選ぶ
+        let best = tree.nodes[0].children.iter()
+            .max_by_key(|&&child| tree.nodes[child].visits)
+            .and_then(|&child| match tree.nodes[child].move_type {
+                Some(MoveType::Put(point)) => Some(point),
+                _ => None,
+            });
+
+        self.tree = Some(tree);
+        best
+    }
+}
+
+impl Node {
+    fn new(board: &Board, turn: PieceType, move_type: Option<MoveType>, parent: Option<usize>) -> Node {
+        let mut untried = board.legal_moves(turn).into_iter().map(MoveType::Put).collect::<Vec<_>>();
+        if untried.is_empty() && !board.is_skip(turn.change()) {
+            untried.push(MoveType::Pass);
+        }
+
+        Node {
+            move_type,
+            turn,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+}
+
+impl Tree {
+    fn new(board: Board, turn: PieceType) -> Tree {
+        let root = Node::new(&board, turn, None, None);
+        Tree {
+            board,
+            nodes: vec![root],
+        }
+    }
+
+    // 新しい局面が根・子・孫のいずれかと一致すればその節点以下を残す
+    fn reuse(self, board: &Board, turn: PieceType) -> Option<Tree> {
+        let mut candidates = vec![(0, self.board.clone())];
+        for depth in 0..2 {
+            let mut next = Vec::new();
+            for (index, position) in candidates {
+                if depth == 0 && position == *board && self.nodes[index].turn == turn {
+                    return Some(self);
+                }
+                for &child in &self.nodes[index].children {
+                    let mut child_board = position.clone();
+                    apply(&mut child_board, self.nodes[index].turn, self.nodes[child].move_type);
+                    if child_board == *board && self.nodes[child].turn == turn {
+                        return Some(self.subtree(child, child_board));
+                    }
+                    next.push((child, child_board));
+                }
+            }
+            candidates = next;
+        }
+
+        None
+    }
+
+    fn subtree(self, root: usize, board: Board) -> Tree {
+        let mut old_nodes = self.nodes.into_iter().map(Some).collect::<Vec<_>>();
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut queue = vec![(root, None)];
+
+        while let Some((index, parent)) = queue.pop() {
+            let Some(mut node) = old_nodes[index].take() else { continue };
+            let new_index = nodes.len();
+            let children = std::mem::take(&mut node.children);
+            node.parent = parent;
+            nodes.push(node);
+
+            if let Some(parent) = parent {
+                nodes[parent].children.push(new_index);
+            }
+            for child in children.into_iter().rev() {
+                queue.push((child, Some(new_index)));
+            }
+        }
+
+        nodes[0].move_type = None;
+        Tree {
+            board,
+            nodes,
+        }
+    }
+}
+
+fn apply(board: &mut Board, turn: PieceType, move_type: Option<MoveType>) {
+    if let Some(MoveType::Put(point)) = move_type {
+        board.make_move(turn, point);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_move() {
+        for size in [6, 8] {
+            let board = Board::new(size, size);
+            let mut player = MctsPlayer::new(Budget::Iterations(200), 1);
+
+            let point = player.choose_move(&board, PieceType::Black).unwrap();
+            assert!(board.can_put_piece(PieceType::Black, &point));
+            assert_eq!(200, player.root_visits());
+
+            // 同じシードなら同じ手
+            let mut other = MctsPlayer::new(Budget::Iterations(200), 1);
+            assert_eq!(Some(point), other.choose_move(&board, PieceType::Black));
+        }
+    }
+
+    #[test]
+    fn test_choose_move_no_budget() {
+        // 探索量が0でも打てる場所があればパスしない
+        let board = Board::new(8, 8);
+        let mut player = MctsPlayer::new(Budget::Iterations(0), 4);
+        let point = player.choose_move(&board, PieceType::Black).unwrap();
+        assert!(board.can_put_piece(PieceType::Black, &point));
+
+        let mut player = MctsPlayer::new(Budget::Time(Duration::ZERO), 4);
+        assert!(player.choose_move(&board, PieceType::Black).is_some());
+    }
+
+    #[test]
+    fn test_tree_reuse() {
+        let mut player = MctsPlayer::new(Budget::Iterations(300), 2);
+
+        let board = Board::new(8, 8);
+        let point = player.choose_move(&board, PieceType::Black).unwrap();
+        let board = board.put_piece(PieceType::Black, point);
+        let reply = board.legal_moves(PieceType::White)[0];
+        let board = board.put_piece(PieceType::White, reply);
+
+        // 前回の探索で訪問した分だけ根の訪問回数が多くなる
+        player.choose_move(&board, PieceType::Black);
+        assert!(player.root_visits() > 300);
+    }
+
+    #[test]
+    fn test_choose_move_pass() {
+        let board = Board::new(4, 4)
+            .put_piece(PieceType::Black, Point::new(2, 1))
+            .put_piece(PieceType::White, Point::new(3, 1))
+            .put_piece(PieceType::Black, Point::new(4, 3))
+            .put_piece(PieceType::White, Point::new(1, 1));
+
+        let mut player = MctsPlayer::new(Budget::Time(Duration::from_millis(10)), 3);
+        assert_eq!(None, player.choose_move(&board, PieceType::Black));
+        assert!(player.choose_move(&board, PieceType::White).is_some());
+    }
+}
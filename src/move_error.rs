@@ -0,0 +1,36 @@
+use crate::point::Point;
+use std::error::Error;
+use std::fmt;
+
+/// 駒を置けなかった理由
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    /// 盤の外
+    OutOfBounds(Point),
+    /// 既に駒が置かれている
+    Occupied(Point),
+    /// 隣接する場所に駒が無い
+    NotAdjacent(Point),
+    /// 返せる駒が無い
+    NoFlips(Point),
+    /// 打てる場所があるためパスできない
+    CannotPass,
+    /// ゲームが終了している
+    GameOver,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::OutOfBounds(point) => write!(f, "{} は盤の外です", point),
+            MoveError::Occupied(point) => write!(f, "{} には既に駒があります", point),
+            MoveError::NotAdjacent(point) => write!(f, "{} の隣に駒がありません", point),
+            MoveError::NoFlips(point) => write!(f, "{} に置いても返せる駒がありません", point),
+            MoveError::CannotPass => write!(f, "打てる場所があるためパスできません"),
+            MoveError::GameOver => write!(f, "ゲームは終了しています"),
+        }
+    }
+}
+
+impl Error for MoveError {}
@@ -0,0 +1,21 @@
+use crate::point::Point;
+use std::fmt;
+
+/// ゲームの一手の種類
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MoveType {
+    /// 駒を置く
+    Put(Point),
+    /// 打てる場所が無いためパスする
+    Pass,
+}
+
+impl fmt::Display for MoveType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveType::Put(point) => write!(f, "{}", point),
+            MoveType::Pass => write!(f, "パス"),
+        }
+    }
+}
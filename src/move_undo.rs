@@ -0,0 +1,55 @@
+use crate::bitboard::Bitboard;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+
+/// # MoveUndo 構造体
+/// 
+/// `Board::make_move`で打った手を`Board::unmake_move`で元に戻すための情報を保持する構造体
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveUndo {
+    pub(crate) piece_type: PieceType,
+    pub(crate) point: Point,
+    pub(crate) flips: Flips,
+}
+
+/// 返った駒の位置。8x8の盤面ではビットボードのまま保持します。
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) enum Flips {
+    Bits(u64),
+    Points(Vec<Point>),
+}
+
+impl MoveUndo {
+    /// 置いた駒の種類を返します。
+    /// 
+    pub fn piece_type(&self) -> PieceType {
+        self.piece_type
+    }
+
+    /// 駒を置いた位置を返します。
+    /// 
+    pub fn point(&self) -> Point {
+        self.point
+    }
+
+    /// 返った駒の位置を返します。
+    /// 駒が置けなかった場合は空になります。
+    /// 
+    pub fn flipped(&self) -> Vec<Point> {
+        match &self.flips {
+            Flips::Bits(bits) => Bitboard::points(*bits).collect(),
+            Flips::Points(points) => points.clone(),
+        }
+    }
+
+    /// 駒が置けずに盤面が変わらなかった場合にtrueを返します。
+    /// 
+    pub fn is_empty(&self) -> bool {
+        match &self.flips {
+            Flips::Bits(bits) => *bits == 0,
+            Flips::Points(points) => points.is_empty(),
+        }
+    }
+}
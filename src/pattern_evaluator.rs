@@ -0,0 +1,306 @@
+//! パターンによる評価関数
+//! 
+//! 辺・隅の3x3・対角線・隅の2x5のマスの並びごとに重みを持ち、その合計で最終石差を予想します。
+//! 重みは進行度(空きマスの数)ごとに分け、`PatternTrainer`で棋譜から学習します。
+//! 
+
+use crate::bitboard::{self, Bitboard};
+use crate::board::Board;
+use crate::evaluator::Evaluator;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+use crate::positional_evaluator::PositionalEvaluator;
+use crate::symmetry::Symmetry;
+use std::error::Error;
+use std::fmt;
+
+/// 進行度の数。空きマス10個ごとに分けます。
+/// 
+pub const PHASES: usize = 6;
+
+/// `evaluate`が返す値の1石あたりの大きさ
+/// 
+pub const SCALE: f32 = 100.0;
+
+const PHASE_WIDTH: usize = 10;
+
+// 重みファイルの先頭と版
+const MAGIC: &[u8; 4] = b"RVPW";
+const VERSION: u16 = 1;
+// 重みファイルでの1石あたりの大きさ
+const FILE_SCALE: f32 = 128.0;
+
+// a1の隅を基準にしたパターンのマス。他の位置のパターンは対称変換で作る
+const EDGE: &[(usize, usize)] = &[(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1), (7, 1), (8, 1)];
+const CORNER_3X3: &[(usize, usize)] = &[(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2), (1, 3), (2, 3), (3, 3)];
+const DIAGONAL: &[(usize, usize)] = &[(1, 1), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6), (7, 7), (8, 8)];
+const CORNER_2X5: &[(usize, usize)] = &[(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (1, 2), (2, 2), (3, 2), (4, 2), (5, 2)];
+const PATTERNS: [&[(usize, usize)]; 4] = [EDGE, CORNER_3X3, DIAGONAL, CORNER_2X5];
+
+// 盤面上の1つのパターン。`offset`は進行度ごとの重みの中でのパターンの開始位置
+#[derive(Clone, Debug)]
+pub(crate) struct Instance {
+    offset: usize,
+    indexes: Vec<u32>,
+}
+
+/// # PatternEvaluator 構造体
+/// 
+/// パターンの重みの合計で最終石差を予想する評価関数
+/// 
+/// `evaluate`は手番から見た予想石差の`SCALE`倍を返します。
+/// パターンは8x8の盤面で定義しているため、それ以外の大きさの盤面は`PositionalEvaluator`で評価します。
+/// 
+#[derive(Clone)]
+pub struct PatternEvaluator {
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+    instances: Vec<Instance>,
+    fallback: PositionalEvaluator,
+}
+
+impl PatternEvaluator {
+    /// 全ての重みが0の評価関数を生成します。
+    /// 
+    pub fn new() -> PatternEvaluator {
+        PatternEvaluator::from_weights(vec![0.0; PHASES * phase_size()], vec![0.0; PHASES])
+    }
+
+    pub(crate) fn from_weights(weights: Vec<f32>, biases: Vec<f32>) -> PatternEvaluator {
+        PatternEvaluator {
+            weights,
+            biases,
+            instances: instances(),
+            fallback: PositionalEvaluator::default(),
+        }
+    }
+
+    pub(crate) fn weights(&self) -> (&[f32], &[f32]) {
+        (&self.weights, &self.biases)
+    }
+
+    pub(crate) fn into_weights(self) -> (Vec<f32>, Vec<f32>) {
+        (self.weights, self.biases)
+    }
+
+    /// `piece_type`の手番から見た最終石差の予想を返します。8x8以外の盤面ではNoneを返します。
+    /// 
+    pub fn predict(&self, board: &Board, piece_type: PieceType) -> Option<f32> {
+        let bitboard = board.bitboard()?;
+        let (phase, features) = features(&self.instances, bitboard, piece_type);
+        let weights = &self.weights[phase * phase_size()..(phase + 1) * phase_size()];
+
+        Some(self.biases[phase] + features.iter().map(|&feature| weights[feature]).sum::<f32>())
+    }
+
+    /// 重みをファイルに保存する形式に変換します。
+    /// 
+    /// 先頭の`RVPW`と版(u16)、進行度の数とパターンの重みの数(u32)に続けて、
+    /// 進行度ごとに定数項とパターンの重みを1/128石単位のi16で並べます。数値は全てリトルエンディアンです。
+    /// 
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(14 + PHASES * (phase_size() + 1) * 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(PHASES as u32).to_le_bytes());
+        bytes.extend_from_slice(&(phase_size() as u32).to_le_bytes());
+
+        for phase in 0..PHASES {
+            let weights = &self.weights[phase * phase_size()..(phase + 1) * phase_size()];
+            for weight in std::iter::once(&self.biases[phase]).chain(weights) {
+                let value = (weight * FILE_SCALE).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// `to_bytes`の形式から重みを読み込みます。
+    /// 
+    pub fn from_bytes(bytes: &[u8]) -> Result<PatternEvaluator, LoadWeightsError> {
+        if bytes.len() < 14 || &bytes[..4] != MAGIC {
+            return Err(LoadWeightsError::InvalidMagic);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            return Err(LoadWeightsError::UnsupportedVersion(version));
+        }
+        let phases = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) as usize;
+        let size = u32::from_le_bytes([bytes[10], bytes[11], bytes[12], bytes[13]]) as usize;
+        if phases != PHASES || size != phase_size() {
+            return Err(LoadWeightsError::InvalidShape { phases, size });
+        }
+        let expected = 14 + phases * (size + 1) * 2;
+        if bytes.len() != expected {
+            return Err(LoadWeightsError::InvalidLength { expected, found: bytes.len() });
+        }
+
+        let mut values = bytes[14..]
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / FILE_SCALE);
+        let mut weights = Vec::with_capacity(phases * size);
+        let mut biases = Vec::with_capacity(phases);
+        for _ in 0..phases {
+            biases.extend(values.by_ref().take(1));
+            weights.extend(values.by_ref().take(size));
+        }
+
+        Ok(PatternEvaluator::from_weights(weights, biases))
+    }
+}
+
+impl Default for PatternEvaluator {
+    fn default() -> Self {
+        PatternEvaluator::new()
+    }
+}
+
+impl Evaluator for PatternEvaluator {
+    fn evaluate(&self, board: &Board, piece_type: PieceType) -> i32 {
+        match self.predict(board, piece_type) {
+            Some(score) => (score * SCALE).round() as i32,
+            None => self.fallback.evaluate(board, piece_type),
+        }
+    }
+}
+
+/// 重みを読み込めなかった理由
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoadWeightsError {
+    /// 重みファイルではない
+    InvalidMagic,
+    /// 対応していない版
+    UnsupportedVersion(u16),
+    /// 進行度の数かパターンの重みの数が違う
+    InvalidShape { phases: usize, size: usize },
+    /// 長さが足りないか余分なデータがある
+    InvalidLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for LoadWeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadWeightsError::InvalidMagic => write!(f, "重みファイルではありません"),
+            LoadWeightsError::UnsupportedVersion(version) => write!(f, "対応していない版です : {}", version),
+            LoadWeightsError::InvalidShape { phases, size } => write!(f, "重みの数が違います : 進行度{} 重み{}", phases, size),
+            LoadWeightsError::InvalidLength { expected, found } => write!(f, "長さが違います : {}バイトのはずが{}バイト", expected, found),
+        }
+    }
+}
+
+impl Error for LoadWeightsError {}
+
+/// 空きマスの数に対応する進行度を返します。
+/// 
+pub fn phase(empties: usize) -> usize {
+    (empties / PHASE_WIDTH).min(PHASES - 1)
+}
+
+// 1つの進行度の重みの数
+pub(crate) fn phase_size() -> usize {
+    PATTERNS.iter().map(|pattern| 3_usize.pow(pattern.len() as u32)).sum()
+}
+
+// 全てのパターンを対称変換で盤面に並べる。マスの組み合わせが同じになる変換は1つにまとめる
+pub(crate) fn instances() -> Vec<Instance> {
+    let mut instances = Vec::new();
+    let mut offset = 0;
+
+    for pattern in PATTERNS {
+        let mut seen = Vec::new();
+        for symmetry in Symmetry::ALL {
+            let indexes = pattern.iter()
+                .map(|&(x, y)| {
+                    let point = symmetry.apply(&Point::new(x, y), bitboard::SIZE, bitboard::SIZE);
+                    Bitboard::index(&point).expect("pattern squares are on the board")
+                })
+                .collect::<Vec<_>>();
+            let bits = indexes.iter().fold(0_u64, |bits, &index| bits | 1 << index);
+            if !seen.contains(&bits) {
+                seen.push(bits);
+                instances.push(Instance { offset, indexes });
+            }
+        }
+        offset += 3_usize.pow(pattern.len() as u32);
+    }
+
+    instances
+}
+
+// 進行度と、進行度ごとの重みの中で使う重みの位置を返す。マスは空き0、手番1、相手2の3進数にする
+pub(crate) fn features(instances: &[Instance], bitboard: &Bitboard, piece_type: PieceType) -> (usize, Vec<usize>) {
+    let own = bitboard.pieces(piece_type);
+    let opponent = bitboard.pieces(piece_type.change());
+
+    let features = instances.iter()
+        .map(|instance| {
+            instance.offset + instance.indexes.iter().fold(0, |index, &square| {
+                let digit = if own >> square & 1 == 1 {
+                    1
+                } else if opponent >> square & 1 == 1 {
+                    2
+                } else {
+                    0
+                };
+                index * 3 + digit
+            })
+        })
+        .collect();
+
+    (phase(bitboard.empties().count_ones() as usize), features)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instances() {
+        let instances = instances();
+        // 辺4、隅の3x3が4、対角線2、隅の2x5が8
+        assert_eq!(18, instances.len());
+        assert_eq!(6561 + 19683 + 6561 + 59049, phase_size());
+
+        let board = Board::new(8, 8);
+        let (phase, features) = features(&instances, board.bitboard().unwrap(), PieceType::Black);
+        assert_eq!(5, phase);
+        // 初期配置では辺と隅は空で、対角線にだけ駒がある
+        assert_eq!(0, features[0]);
+        let diagonal = instances.iter().position(|instance| instance.offset == 6561 + 19683).unwrap();
+        assert_ne!(6561 + 19683, features[diagonal]);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let evaluator = PatternEvaluator::new();
+        assert_eq!(Some(0.0), evaluator.predict(&Board::new(8, 8), PieceType::Black));
+
+        // 8x8以外の盤面はPositionalEvaluatorで評価する
+        let board = Board::new(6, 6);
+        assert_eq!(None, evaluator.predict(&board, PieceType::Black));
+        assert_eq!(PositionalEvaluator::default().evaluate(&board, PieceType::Black), evaluator.evaluate(&board, PieceType::Black));
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut weights = vec![0.0; PHASES * phase_size()];
+        weights[3] = 1.5;
+        weights[phase_size() + 7] = -0.25;
+        let evaluator = PatternEvaluator::from_weights(weights, vec![0.5; PHASES]);
+
+        let bytes = evaluator.to_bytes();
+        let loaded = PatternEvaluator::from_bytes(&bytes).unwrap();
+        assert_eq!(evaluator.weights, loaded.weights);
+        assert_eq!(evaluator.biases, loaded.biases);
+
+        assert_eq!(Err(LoadWeightsError::InvalidMagic), PatternEvaluator::from_bytes(b"XXXX").map(|_| ()));
+        assert_eq!(
+            Err(LoadWeightsError::InvalidLength { expected: bytes.len(), found: bytes.len() - 1 }),
+            PatternEvaluator::from_bytes(&bytes[..bytes.len() - 1]).map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! パターン評価関数の学習
+//! 
+
+use crate::board::{Board, ParseBoardError};
+use crate::pattern_evaluator::{self, Instance, PatternEvaluator, PHASES};
+use crate::piece_type::PieceType;
+use std::error::Error;
+use std::fmt;
+
+/// # Sample 構造体
+/// 
+/// 学習に使う局面と、その局面から打ち進めた最終石差
+/// 
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Sample {
+    /// 局面
+    pub board: Board,
+    /// 手番
+    pub turn: PieceType,
+    /// 黒から見た最終石差
+    pub score: i32,
+}
+
+/// 1行に`局面 手番 石差`を並べたテキストから学習用の局面を読み込みます。
+/// 局面と手番は`Board::to_position_str`の形式、石差は黒から見た値です。`#`以降はコメントです。
+/// 
+pub fn parse_samples(s: &str, x_size: usize, y_size: usize) -> Result<Vec<Sample>, ParseSampleError> {
+    let mut samples = Vec::new();
+
+    for (index, line) in s.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (position, score) = line.rsplit_once(char::is_whitespace).ok_or(ParseSampleError::InvalidLine(line_number))?;
+        let score = score.parse::<i32>().map_err(|_| ParseSampleError::InvalidScore(line_number, score.to_string()))?;
+        let (board, turn) = Board::from_position_str(position, x_size, y_size)
+            .map_err(|error| ParseSampleError::InvalidPosition(line_number, error))?;
+        samples.push(Sample { board, turn, score });
+    }
+
+    Ok(samples)
+}
+
+/// `parse_samples`で読み込める形式の1行を返します。
+/// 
+pub fn write_sample(sample: &Sample) -> String {
+    format!("{} {:+}", sample.board.to_position_str(sample.turn), sample.score)
+}
+
+/// 学習用の局面を読み込めなかった理由。行番号は1始まりです。
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseSampleError {
+    /// `局面 手番 石差`の形式ではない
+    InvalidLine(usize),
+    /// 局面が不正
+    InvalidPosition(usize, ParseBoardError),
+    /// 石差が整数ではない
+    InvalidScore(usize, String),
+}
+
+impl fmt::Display for ParseSampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSampleError::InvalidLine(line) => write!(f, "{}行目: `局面 手番 石差`の形式ではありません", line),
+            ParseSampleError::InvalidPosition(line, error) => write!(f, "{}行目: {}", line, error),
+            ParseSampleError::InvalidScore(line, score) => write!(f, "{}行目: 石差が不正です : {}", line, score),
+        }
+    }
+}
+
+impl Error for ParseSampleError {}
+
+// 学習用に変換した局面。石差は手番から見た値
+struct Row {
+    phase: usize,
+    features: Vec<usize>,
+    target: f32,
+}
+
+/// # PatternTrainer 構造体
+/// 
+/// 局面と最終石差の組から`PatternEvaluator`の重みを最小二乗法で学習する構造体
+/// 
+/// 勾配降下の各重みの更新量は、その重みを使った局面の数で割って均します。
+/// 出現の少ないパターンの重みが大きく振れないよう、更新量の分母には`smoothing`を加えます。
+/// 
+pub struct PatternTrainer {
+    instances: Vec<Instance>,
+    rows: Vec<Row>,
+    learning_rate: f32,
+    smoothing: f32,
+}
+
+impl PatternTrainer {
+    pub fn new() -> PatternTrainer {
+        PatternTrainer {
+            instances: pattern_evaluator::instances(),
+            rows: Vec::new(),
+            learning_rate: 1.0,
+            smoothing: 1.0,
+        }
+    }
+
+    /// 学習率を設定します。既定値は1です。
+    /// 
+    pub fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    /// 更新量の分母に加える値を設定します。既定値は1です。
+    /// 
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing;
+    }
+
+    /// 学習用の局面を追加します。8x8以外の盤面は使えないためfalseを返します。
+    /// 
+    pub fn add(&mut self, sample: &Sample) -> bool {
+        let bitboard = match sample.board.bitboard() {
+            Some(bitboard) => bitboard,
+            None => return false,
+        };
+
+        let (phase, features) = pattern_evaluator::features(&self.instances, bitboard, sample.turn);
+        let target = if sample.turn == PieceType::Black { sample.score } else { -sample.score };
+        self.rows.push(Row { phase, features, target: target as f32 });
+        true
+    }
+
+    /// 追加した局面の数を返します。
+    /// 
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 局面が1つも追加されていない場合にtrueを返します。
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// `epochs`回全ての局面で重みを更新し、学習した評価関数を返します。
+    /// 
+    pub fn train(&self, epochs: usize) -> PatternEvaluator {
+        self.train_from(PatternEvaluator::new(), epochs)
+    }
+
+    /// `evaluator`の重みから学習を続け、学習した評価関数を返します。
+    /// 
+    pub fn train_from(&self, evaluator: PatternEvaluator, epochs: usize) -> PatternEvaluator {
+        let size = pattern_evaluator::phase_size();
+        let (mut weights, mut biases) = evaluator.into_weights();
+
+        let mut counts = vec![0_u32; PHASES * size];
+        let mut bias_counts = [0_u32; PHASES];
+        for row in &self.rows {
+            bias_counts[row.phase] += 1;
+            for &feature in &row.features {
+                counts[row.phase * size + feature] += 1;
+            }
+        }
+
+        let mut gradients = vec![0.0_f32; PHASES * size];
+        for _ in 0..epochs {
+            gradients.iter_mut().for_each(|gradient| *gradient = 0.0);
+            let mut bias_gradients = [0.0_f32; PHASES];
+
+            for row in &self.rows {
+                let offset = row.phase * size;
+                let prediction = biases[row.phase] + row.features.iter().map(|&feature| weights[offset + feature]).sum::<f32>();
+                let error = row.target - prediction;
+
+                bias_gradients[row.phase] += error;
+                for &feature in &row.features {
+                    gradients[offset + feature] += error;
+                }
+            }
+
+            // 1つの局面で使う重みの数で割り、全ての重みを同時に動かしても行き過ぎないようにする
+            let rate = self.learning_rate / (self.instances.len() + 1) as f32;
+            for ((weight, gradient), &count) in weights.iter_mut().zip(&gradients).zip(&counts) {
+                if count > 0 {
+                    *weight += rate * gradient / (count as f32 + self.smoothing);
+                }
+            }
+            for phase in 0..PHASES {
+                if bias_counts[phase] > 0 {
+                    biases[phase] += rate * bias_gradients[phase] / (bias_counts[phase] as f32 + self.smoothing);
+                }
+            }
+        }
+
+        PatternEvaluator::from_weights(weights, biases)
+    }
+
+    /// 追加した局面での`evaluator`の予想と最終石差の平均二乗誤差を返します。
+    /// 
+    pub fn mean_squared_error(&self, evaluator: &PatternEvaluator) -> f64 {
+        if self.rows.is_empty() {
+            return 0.0;
+        }
+
+        let size = pattern_evaluator::phase_size();
+        let (weights, biases) = evaluator.weights();
+        let total = self.rows.iter()
+            .map(|row| {
+                let prediction = biases[row.phase] + row.features.iter().map(|&feature| weights[row.phase * size + feature]).sum::<f32>();
+                ((row.target - prediction) as f64).powi(2)
+            })
+            .sum::<f64>();
+
+        total / self.rows.len() as f64
+    }
+}
+
+impl Default for PatternTrainer {
+    fn default() -> Self {
+        PatternTrainer::new()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+    use crate::point::Point;
+    use crate::random_player::RandomPlayer;
+
+    // 無作為に打った対局の局面を、隅の数の差の10倍を最終石差として集める
+    fn corner_samples(games: u64) -> Vec<Sample> {
+        let corners = [Point::new(1, 1), Point::new(8, 1), Point::new(1, 8), Point::new(8, 8)];
+        let mut samples = Vec::new();
+
+        for seed in 0..games {
+            let mut player = RandomPlayer::new(seed);
+            let mut board = Board::new(8, 8);
+            let mut turn = PieceType::Black;
+            while !board.is_end() {
+                if let Some(point) = player.choose_move(&board, turn) {
+                    board = board.put_piece(turn, point);
+                }
+                turn = turn.change();
+
+                let score = corners.iter()
+                    .map(|corner| match board.piece_at(corner) {
+                        Some(PieceType::Black) => 10,
+                        Some(PieceType::White) => -10,
+                        None => 0,
+                    })
+                    .sum();
+                samples.push(Sample { board: board.clone(), turn, score });
+            }
+        }
+
+        samples
+    }
+
+    #[test]
+    fn test_parse_samples() {
+        let sample = Sample { board: Board::new(8, 8), turn: PieceType::White, score: -6 };
+        let line = write_sample(&sample);
+        assert!(parse_samples(&format!("# 学習用\n{}\n", line), 8, 8).unwrap() == vec![sample]);
+
+        assert!(matches!(parse_samples("X", 8, 8), Err(ParseSampleError::InvalidLine(1))));
+        assert!(matches!(parse_samples(&format!("{} x", "-".repeat(64) + " X"), 8, 8), Err(ParseSampleError::InvalidScore(1, _))));
+        assert!(matches!(parse_samples("XO 0", 8, 8), Err(ParseSampleError::InvalidPosition(1, _))));
+    }
+
+    #[test]
+    fn test_train() {
+        let mut trainer = PatternTrainer::new();
+        for sample in corner_samples(20) {
+            assert!(trainer.add(&sample));
+        }
+        assert!(!trainer.add(&Sample { board: Board::new(6, 6), turn: PieceType::Black, score: 0 }));
+
+        let untrained = PatternEvaluator::new();
+        let trained = trainer.train(50);
+        let before = trainer.mean_squared_error(&untrained);
+        let after = trainer.mean_squared_error(&trained);
+        assert!(after < before / 10.0, "before {} after {}", before, after);
+
+        // 続きから学習すると誤差はさらに小さくなる
+        let continued = trainer.train_from(trained, 10);
+        assert!(trainer.mean_squared_error(&continued) <= after);
+    }
+}
@@ -0,0 +1,146 @@
+//! 合法手生成の検証に使うperft
+//! 
+//! 指定した手数だけ全ての手を打ち進め、末端の局面の数を数えます。
+//! 打てる場所が無い場合はパスを1手として数え、両者とも打てない局面はその時点で末端として1つと数えます。
+//! 
+
+use crate::board::Board;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+
+/// `side`の手番から`depth`手打ち進めた末端の局面の数を返します。
+/// 
+pub fn perft(board: &Board, side: PieceType, depth: usize) -> u64 {
+    let mut board = board.clone();
+    count(&mut board, side, depth)
+}
+
+/// `perft`の数を最初の手ごとに分けて返します。パスは`None`で、最初の手は盤面の左上から行ごとに並んだ順です。
+/// 
+/// `depth`が0の場合と終局した局面では、その局面自体を`(None, 1)`の1つだけで返します。
+/// どの場合も数の合計は`perft`と一致します。
+/// 
+pub fn divide(board: &Board, side: PieceType, depth: usize) -> Vec<(Option<Point>, u64)> {
+    let mut board = board.clone();
+    if depth == 0 {
+        return vec![(None, 1)];
+    }
+
+    let moves = board.legal_moves(side);
+    if moves.is_empty() {
+        if board.is_skip(side.change()) {
+            return vec![(None, 1)];
+        }
+        return vec![(None, count(&mut board, side.change(), depth - 1))];
+    }
+
+    moves.into_iter()
+        .map(|point| {
+            let undo = board.make_move(side, point);
+            let nodes = count(&mut board, side.change(), depth - 1);
+            board.unmake_move(undo);
+            (Some(point), nodes)
+        })
+        .collect()
+}
+
+fn count(board: &mut Board, side: PieceType, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = board.legal_moves(side);
+    if moves.is_empty() {
+        // 両者とも打てない場合は終局
+        if board.is_skip(side.change()) {
+            return 1;
+        }
+        return count(board, side.change(), depth - 1);
+    }
+
+    // 最後の1手は打たずに数だけ数える
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves.into_iter()
+        .map(|point| {
+            let undo = board.make_move(side, point);
+            let nodes = count(board, side.change(), depth - 1);
+            board.unmake_move(undo);
+            nodes
+        })
+        .sum()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 8x8の初期配置からの既知の数。1手目から順に並べる
+    const REFERENCE: [u64; 9] = [4, 12, 56, 244, 1396, 8200, 55092, 390216, 3005288];
+
+    #[test]
+    fn test_perft() {
+        let board = Board::new(8, 8);
+        assert_eq!(1, perft(&board, PieceType::Black, 0));
+        for (depth, &expected) in REFERENCE.iter().enumerate().take(7) {
+            assert_eq!(expected, perft(&board, PieceType::Black, depth + 1), "depth {}", depth + 1);
+        }
+    }
+
+    #[test]
+    fn test_perft_general() {
+        // HashMapの盤面で手を探す処理もビットボードと同じ数になる
+        let board = Board::new_general(8, 8);
+        for (depth, &expected) in REFERENCE.iter().enumerate().take(5) {
+            assert_eq!(expected, perft(&board, PieceType::Black, depth + 1), "depth {}", depth + 1);
+        }
+    }
+
+    #[test]
+    #[ignore = "時間がかかるため`cargo test --release -- --ignored`で実行する"]
+    fn test_perft_deep() {
+        let board = Board::new(8, 8);
+        assert_eq!(REFERENCE[8], perft(&board, PieceType::Black, 9));
+    }
+
+    #[test]
+    fn test_divide() {
+        let board = Board::new(8, 8);
+        let counts = divide(&board, PieceType::Black, 4);
+        assert_eq!(board.legal_moves(PieceType::Black), counts.iter().filter_map(|&(point, _)| point).collect::<Vec<_>>());
+        assert_eq!(REFERENCE[3], counts.iter().map(|&(_, nodes)| nodes).sum::<u64>());
+        // 初期配置は対称なので、どの手から始めても同じ数になる
+        assert!(counts.iter().all(|&(_, nodes)| nodes == REFERENCE[3] / 4));
+
+        // パスは1手として数える
+        let (board, turn) = Board::from_position_str("XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXOO- O", 8, 8).unwrap();
+        assert!(board.is_skip(turn));
+        assert_eq!(vec![(None, 1)], divide(&board, turn, 2));
+        assert_eq!(1, perft(&board, turn, 2));
+
+        // 終局した局面は手数が残っていても1つと数える
+        let (board, turn) = Board::from_position_str("XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX- O", 8, 8).unwrap();
+        assert_eq!(1, perft(&board, turn, 3));
+        assert_eq!(vec![(None, 1)], divide(&board, turn, 3));
+    }
+
+    #[test]
+    fn test_divide_sum() {
+        // 手数が0の場合や終局した局面も含め、合計は常にperftと一致する
+        let positions = [
+            (Board::new(8, 8), PieceType::Black),
+            Board::from_position_str("XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXOO- O", 8, 8).unwrap(),
+            Board::from_position_str("XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX- O", 8, 8).unwrap(),
+        ];
+        for (board, turn) in &positions {
+            for depth in 0..4 {
+                let total: u64 = divide(board, *turn, depth).iter().map(|&(_, nodes)| nodes).sum();
+                assert_eq!(perft(board, *turn, depth), total, "depth {}", depth);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt;
 
 /// オセロの駒の種類
 /// 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum PieceType {
     Black,
     White,
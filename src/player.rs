@@ -0,0 +1,12 @@
+use crate::board::Board;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+
+/// 手を選ぶプレイヤー
+/// 
+pub trait Player {
+    /// `piece_type`の手番で置く場所を選びます。
+    /// 打てる場所が無い場合はNoneを返します。
+    /// 
+    fn choose_move(&mut self, board: &Board, piece_type: PieceType) -> Option<Point>;
+}
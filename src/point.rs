@@ -1,8 +1,10 @@
+use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
 /// x座標とy座標を保持するPoint構造体
 /// 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Point {
     pub x: usize,
     pub y: usize,
@@ -15,10 +17,148 @@ impl Point {
             y,
         }
     }
+
+    /// "f5"のようなオセロの棋譜表記を返します。
+    /// 列はaから順に割り当て、zの次はaa、abと続きます。
+    /// 盤外(番兵)の位置のように表記できない場合はNoneが返却されます。
+    /// 
+    pub fn to_notation(&self) -> Option<String> {
+        if self.x == 0 || self.y == 0 {
+            return None;
+        }
+
+        let mut column = Vec::new();
+        let mut x = self.x;
+        while x > 0 {
+            x -= 1;
+            column.push((b'a' + (x % 26) as u8) as char);
+            x /= 26;
+        }
+
+        Some(format!("{}{}", column.iter().rev().collect::<String>(), self.y))
+    }
 }
 
+/// "f5"のような棋譜表記で表示します。
+/// 盤外(番兵)の位置は表記できないため、内部の座標をそのまま表示します。
+/// 
 impl fmt::Display for Point {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "座標:{},{}", self.x, self.y)
+        match self.to_notation() {
+            Some(notation) => write!(f, "{}", notation),
+            None => write!(f, "座標:{},{}", self.x, self.y),
+        }
+    }
+}
+
+/// "f5"のようなオセロの棋譜表記から`Point`を生成します。
+/// 大文字の列も受け付けます。
+/// 
+impl FromStr for Point {
+    type Err = ParsePointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParsePointError::Empty);
+        }
+
+        let row_start = s.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(s.len());
+        let (column, row) = s.split_at(row_start);
+
+        if let Some(c) = row.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(ParsePointError::InvalidCharacter(c));
+        }
+        if column.is_empty() {
+            return Err(ParsePointError::MissingColumn);
+        }
+        if row.is_empty() {
+            return Err(ParsePointError::MissingRow);
+        }
+
+        let mut x: usize = 0;
+        for c in column.chars() {
+            let digit = (c.to_ascii_lowercase() as u8 - b'a') as usize + 1;
+            x = x.checked_mul(26)
+                .and_then(|x| x.checked_add(digit))
+                .ok_or(ParsePointError::InvalidColumn)?;
+        }
+
+        match row.parse::<usize>() {
+            Ok(y) if y > 0 => Ok(Point::new(x, y)),
+            _ => Err(ParsePointError::InvalidRow),
+        }
+    }
+}
+
+/// 棋譜表記の座標を読み取れなかった理由
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParsePointError {
+    /// 空文字列
+    Empty,
+    /// 列の英字が無い
+    MissingColumn,
+    /// 行の数字が無い
+    MissingRow,
+    /// 列が大きすぎる
+    InvalidColumn,
+    /// 行が0または大きすぎる
+    InvalidRow,
+    /// 使えない文字が含まれている
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for ParsePointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsePointError::Empty => write!(f, "座標が入力されていません"),
+            ParsePointError::MissingColumn => write!(f, "列(a〜)が指定されていません"),
+            ParsePointError::MissingRow => write!(f, "行(1〜)が指定されていません"),
+            ParsePointError::InvalidColumn => write!(f, "列の値が不正です"),
+            ParsePointError::InvalidRow => write!(f, "行の値が不正です"),
+            ParsePointError::InvalidCharacter(c) => write!(f, "座標に使えない文字です : {}", c),
+        }
+    }
+}
+
+impl Error for ParsePointError {}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Ok(Point::new(6, 5)), "f5".parse());
+        assert_eq!(Ok(Point::new(4, 3)), "D3".parse());
+        assert_eq!(Ok(Point::new(1, 12)), " a12 ".parse());
+        assert_eq!(Ok(Point::new(27, 1)), "aa1".parse());
+
+        assert_eq!(Err(ParsePointError::Empty), "".parse::<Point>());
+        assert_eq!(Err(ParsePointError::MissingColumn), "5".parse::<Point>());
+        assert_eq!(Err(ParsePointError::MissingRow), "f".parse::<Point>());
+        assert_eq!(Err(ParsePointError::InvalidRow), "f0".parse::<Point>());
+        assert_eq!(Err(ParsePointError::InvalidCharacter('f')), "5f".parse::<Point>());
+        assert_eq!(Err(ParsePointError::InvalidCharacter('-')), "f-5".parse::<Point>());
+    }
+
+    #[test]
+    fn test_to_notation() {
+        assert_eq!(Some("f5".to_string()), Point::new(6, 5).to_notation());
+        assert_eq!(Some("h8".to_string()), Point::new(8, 8).to_notation());
+        assert_eq!(Some("z10".to_string()), Point::new(26, 10).to_notation());
+        assert_eq!(Some("aa1".to_string()), Point::new(27, 1).to_notation());
+        assert_eq!(None, Point::new(0, 5).to_notation());
+
+        assert_eq!("d3", Point::new(4, 3).to_string());
+        assert_eq!("座標:0,5", Point::new(0, 5).to_string());
+
+        for x in 1..=60 {
+            let point = Point::new(x, 3);
+            assert_eq!(Ok(point), point.to_notation().unwrap().parse());
+        }
     }
 }
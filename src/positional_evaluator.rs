@@ -0,0 +1,264 @@
+use crate::board::Board;
+use crate::evaluator::Evaluator;
+use crate::piece_type::PieceType;
+use crate::point::Point;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// # Weights 構造体
+/// 
+/// `PositionalEvaluator`の各評価項目の重み
+/// 
+/// `corner = 100`のような`名前 = 値`の行を並べた文字列から読み込めるため、
+/// ファイルに保存しておけば再コンパイルせずに調整できます。`#`以降はコメントです。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Weights {
+    /// 隅
+    pub corner: i32,
+    /// 隅の斜め隣(X打ち)
+    pub x_square: i32,
+    /// 隅の辺上の隣(C打ち)
+    pub c_square: i32,
+    /// 隅以外の辺
+    pub edge: i32,
+    /// 辺以外
+    pub inner: i32,
+    /// 打てる場所の数の差
+    pub mobility: i32,
+    /// 相手の駒に隣接する空きマスの数の差
+    pub potential_mobility: i32,
+    /// 空きマスに隣接する駒の数の差(少ない方が有利)
+    pub frontier: i32,
+    /// 空きマスが奇数で手番の場合に加える値
+    pub parity: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            corner: 100,
+            x_square: -50,
+            c_square: -20,
+            edge: 10,
+            inner: 1,
+            mobility: 15,
+            potential_mobility: 5,
+            frontier: 5,
+            parity: 10,
+        }
+    }
+}
+
+impl Weights {
+    fn entries(&mut self) -> [(&'static str, &mut i32); 9] {
+        [
+            ("corner", &mut self.corner),
+            ("x_square", &mut self.x_square),
+            ("c_square", &mut self.c_square),
+            ("edge", &mut self.edge),
+            ("inner", &mut self.inner),
+            ("mobility", &mut self.mobility),
+            ("potential_mobility", &mut self.potential_mobility),
+            ("frontier", &mut self.frontier),
+            ("parity", &mut self.parity),
+        ]
+    }
+}
+
+/// `名前 = 値`の行から重みを読み込みます。指定していない項目は既定値になります。
+/// 
+impl FromStr for Weights {
+    type Err = ParseWeightsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Weights::default();
+
+        for (index, line) in s.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or(ParseWeightsError::InvalidLine(line_number))?;
+            let (key, value) = (key.trim(), value.trim());
+            let value = value.parse::<i32>().map_err(|_| ParseWeightsError::InvalidValue(line_number, value.to_string()))?;
+
+            let mut entries = weights.entries();
+            match entries.iter_mut().find(|(name, _)| *name == key) {
+                Some((_, weight)) => **weight = value,
+                None => return Err(ParseWeightsError::UnknownKey(line_number, key.to_string())),
+            }
+        }
+
+        Ok(weights)
+    }
+}
+
+/// `FromStr`で読み込める形式で重みを書き出します。
+/// 
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut weights = *self;
+        for (name, weight) in weights.entries() {
+            writeln!(f, "{} = {}", name, weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// 重みを読み込めなかった理由。行番号は1始まりです。
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseWeightsError {
+    /// `名前 = 値`の形式ではない
+    InvalidLine(usize),
+    /// 知らない項目名
+    UnknownKey(usize, String),
+    /// 値が整数ではない
+    InvalidValue(usize, String),
+}
+
+impl fmt::Display for ParseWeightsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWeightsError::InvalidLine(line) => write!(f, "{}行目: `名前 = 値`の形式ではありません", line),
+            ParseWeightsError::UnknownKey(line, key) => write!(f, "{}行目: 知らない項目です : {}", line, key),
+            ParseWeightsError::InvalidValue(line, value) => write!(f, "{}行目: 値が不正です : {}", line, value),
+        }
+    }
+}
+
+impl Error for ParseWeightsError {}
+
+/// # PositionalEvaluator 構造体
+/// 
+/// マスの位置の重み、打てる場所の数(着手可能数)、開放度、偶数理論を組み合わせた評価関数
+/// 
+/// マスの重みは盤面の大きさから隅・X打ち・C打ち・辺・それ以外に分類して決めるため、
+/// 8x8以外の盤面でも使えます。
+/// 
+#[derive(Clone, Default)]
+pub struct PositionalEvaluator {
+    weights: Weights,
+}
+
+impl PositionalEvaluator {
+    pub fn new(weights: Weights) -> PositionalEvaluator {
+        PositionalEvaluator {
+            weights,
+        }
+    }
+
+    /// 評価に使っている重みを返します。
+    /// 
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    /// 盤面の大きさに応じたマスの重みを返します。
+    /// 
+    pub fn square_weight(&self, point: &Point, x_size: usize, y_size: usize) -> i32 {
+        let x_edge = point.x == 1 || point.x == x_size;
+        let y_edge = point.y == 1 || point.y == y_size;
+        let x_next = point.x == 2 || point.x + 1 == x_size;
+        let y_next = point.y == 2 || point.y + 1 == y_size;
+
+        if x_edge && y_edge {
+            self.weights.corner
+        } else if x_next && y_next {
+            self.weights.x_square
+        } else if (x_edge && y_next) || (y_edge && x_next) {
+            self.weights.c_square
+        } else if x_edge || y_edge {
+            self.weights.edge
+        } else {
+            self.weights.inner
+        }
+    }
+}
+
+impl Evaluator for PositionalEvaluator {
+    fn evaluate(&self, board: &Board, piece_type: PieceType) -> i32 {
+        let opponent = piece_type.change();
+        let (x_size, y_size) = (board.x_size(), board.y_size());
+
+        let mut score = 0;
+        for y in 1..=y_size {
+            for x in 1..=x_size {
+                let point = Point::new(x, y);
+                match board.piece_at(&point) {
+                    Some(p) if p == piece_type => score += self.square_weight(&point, x_size, y_size),
+                    Some(_) => score -= self.square_weight(&point, x_size, y_size),
+                    None => (),
+                }
+            }
+        }
+
+        let difference = |own: usize, other: usize| own as i32 - other as i32;
+        score += self.weights.mobility * difference(board.legal_move_count(piece_type), board.legal_move_count(opponent));
+        score += self.weights.potential_mobility * difference(board.potential_mobility(piece_type), board.potential_mobility(opponent));
+        score -= self.weights.frontier * difference(board.frontier_count(piece_type), board.frontier_count(opponent));
+
+        // 空きマスが奇数なら手番側が最後に打てる
+        if board.empty_count() % 2 == 1 {
+            score += self.weights.parity;
+        } else {
+            score -= self.weights.parity;
+        }
+
+        score
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_from_str() {
+        let weights = "# 調整用\ncorner = 200\n\nmobility=3 # 着手可能数\n".parse::<Weights>().unwrap();
+        assert_eq!(200, weights.corner);
+        assert_eq!(3, weights.mobility);
+        assert_eq!(Weights::default().edge, weights.edge);
+
+        // 書き出したものを読み込むと元に戻る
+        assert_eq!(Ok(weights), weights.to_string().parse());
+
+        assert_eq!(Err(ParseWeightsError::InvalidLine(1)), "corner".parse::<Weights>());
+        assert_eq!(Err(ParseWeightsError::UnknownKey(2, "foo".to_string())), "corner = 1\nfoo = 2".parse::<Weights>());
+        assert_eq!(Err(ParseWeightsError::InvalidValue(1, "a".to_string())), "corner = a".parse::<Weights>());
+    }
+
+    #[test]
+    fn test_square_weight() {
+        let evaluator = PositionalEvaluator::default();
+        let weights = Weights::default();
+
+        assert_eq!(weights.corner, evaluator.square_weight(&Point::new(8, 1), 8, 8));
+        assert_eq!(weights.x_square, evaluator.square_weight(&Point::new(7, 2), 8, 8));
+        assert_eq!(weights.c_square, evaluator.square_weight(&Point::new(1, 2), 8, 8));
+        assert_eq!(weights.c_square, evaluator.square_weight(&Point::new(9, 10), 10, 10));
+        assert_eq!(weights.edge, evaluator.square_weight(&Point::new(4, 8), 8, 8));
+        assert_eq!(weights.inner, evaluator.square_weight(&Point::new(4, 4), 8, 8));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let evaluator = PositionalEvaluator::default();
+
+        // 初期配置は対称なので偶数理論の分だけ不利
+        let board = Board::new(8, 8);
+        assert_eq!(-Weights::default().parity, evaluator.evaluate(&board, PieceType::Black));
+
+        // 隅を取った方が有利
+        let position = "XO------".to_string() + &"-".repeat(56) + " X";
+        let (board, turn) = Board::from_position_str(&position, 8, 8).unwrap();
+        assert!(evaluator.evaluate(&board, turn) > 0);
+        assert!(evaluator.evaluate(&board, turn.change()) < 0);
+    }
+}
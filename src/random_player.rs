@@ -0,0 +1,53 @@
+use crate::board::Board;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::point::Point;
+use crate::rng::Rng;
+
+/// # RandomPlayer 構造体
+/// 
+/// 打てる場所から無作為に選ぶプレイヤー。同じシードなら同じ手を選びます。
+/// 
+pub struct RandomPlayer {
+    rng: Rng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> RandomPlayer {
+        RandomPlayer {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, board: &Board, piece_type: PieceType) -> Option<Point> {
+        let moves = board.legal_moves(piece_type);
+        if moves.is_empty() {
+            None
+        } else {
+            Some(moves[self.rng.gen_range(moves.len())])
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_move() {
+        let board = Board::new(8, 8);
+        let mut player1 = RandomPlayer::new(1);
+        let mut player2 = RandomPlayer::new(1);
+
+        for _ in 0..10 {
+            let point = player1.choose_move(&board, PieceType::Black);
+            assert!(board.can_put_piece(PieceType::Black, &point.unwrap()));
+            // 同じシードなら同じ手
+            assert_eq!(point, player2.choose_move(&board, PieceType::Black));
+        }
+    }
+}
@@ -0,0 +1,39 @@
+/// # Rng 構造体
+/// 
+/// シードから同じ乱数列を再現できる疑似乱数生成器(xorshift64*)
+/// 
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// シードを指定して生成します。
+    /// 
+    pub fn new(seed: u64) -> Rng {
+        // 0の状態にならないようsplitmix64で初期化する
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Rng {
+            state: if z == 0 { 0x9e37_79b9_7f4a_7c15 } else { z },
+        }
+    }
+
+    /// 次の乱数を返します。
+    /// 
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// `0..n`の範囲の乱数を返します。`n`は1以上を指定します。
+    /// 
+    pub fn gen_range(&mut self, n: usize) -> usize {
+        (((self.next_u64() >> 32) * n as u64) >> 32) as usize
+    }
+}
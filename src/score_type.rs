@@ -0,0 +1,13 @@
+/// 評価値の種類
+/// 
+/// 種類によって評価値の単位が違うため、比べたり学習に使ったりする前に確認します。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ScoreType {
+    /// 評価関数の値。単位は評価関数によります
+    Evaluation,
+    /// 終局まで読み切った値。勝ちは`SCORE_WIN + 石差`、負けは`-SCORE_WIN + 石差`、引き分けは0です
+    Exact,
+    /// 定石に登録された石差
+    Book,
+}
@@ -0,0 +1,590 @@
+//! αβ法(negamax)による探索
+//! 
+
+use crate::board::Board;
+use crate::book::Book;
+use crate::endgame::EndgameSolver;
+use crate::evaluator::Evaluator;
+use crate::move_type::MoveType;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::point::Point;
+use crate::positional_evaluator::PositionalEvaluator;
+use crate::score_type::ScoreType;
+use crate::time_control::TimeControl;
+use crate::transposition_table::{Bound, Entry, TranspositionTable};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// 終局した局面の評価値の基準。勝ちは`SCORE_WIN + 石差`、負けは`-SCORE_WIN + 石差`になります。
+/// 
+pub const SCORE_WIN: i32 = 1_000_000;
+
+/// 完全読みに切り替える空きマスの数の既定値
+/// 
+pub const DEFAULT_ENDGAME_EMPTIES: usize = 10;
+
+/// 置換表の件数の既定値
+/// 
+pub const DEFAULT_TABLE_SIZE: usize = 1 << 16;
+
+const INFINITY: i32 = i32::MAX / 2;
+
+// 時間切れを確認する間隔のノード数
+const CHECK_INTERVAL: u64 = 1024;
+
+/// # SearchResult 構造体
+/// 
+/// 探索の結果
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SearchResult {
+    /// 最善手。打てる場所が無くパスする場合はNone
+    pub best_move: Option<Point>,
+    /// 手番から見た評価値
+    pub score: i32,
+    /// 評価値の種類
+    pub score_type: ScoreType,
+    /// 最善応手手順(読み筋)
+    pub pv: Vec<MoveType>,
+    /// 探索したノード数
+    pub nodes: u64,
+    /// 探索した深さ
+    pub depth: usize,
+}
+
+/// # Search 構造体
+/// 
+/// 指定した深さまでαβ法で探索する構造体
+/// 
+/// 末端の局面は`Evaluator`で評価します。
+/// 空きマスが`endgame_empties`以下の局面は`EndgameSolver`で最後まで読み切ります。
+/// 探索した局面は置換表に保存し、探索を繰り返しても残します。
+/// 
+/// スレッド数を2以上にすると、置換表を共有した補助スレッドが同時に同じ局面を探索します(Lazy SMP)。
+/// 補助スレッドの結果は置換表を通してのみ使われるため、スレッド数が1の場合の結果は常に同じです。
+/// 
+/// 定石を設定すると、定石に登録されている局面では探索せずに定石の手を返します。
+/// 
+pub struct Search<E: Evaluator = PositionalEvaluator> {
+    depth: usize,
+    endgame_empties: usize,
+    threads: usize,
+    evaluator: E,
+    table: TranspositionTable,
+    book: Option<Book>,
+}
+
+// 1つのスレッドで探索する状態
+struct Worker<'a, E: Evaluator> {
+    evaluator: &'a E,
+    table: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl Search {
+    /// `depth`手先まで読む探索を生成します。評価には既定の`PositionalEvaluator`を使います。
+    /// 
+    pub fn new(depth: usize) -> Search {
+        Search::with_evaluator(depth, PositionalEvaluator::default())
+    }
+}
+
+impl<E: Evaluator + Sync> Search<E> {
+    /// `depth`手先まで読み、`evaluator`で評価する探索を生成します。
+    /// 
+    pub fn with_evaluator(depth: usize, evaluator: E) -> Search<E> {
+        Search {
+            depth,
+            endgame_empties: DEFAULT_ENDGAME_EMPTIES,
+            threads: 1,
+            evaluator,
+            table: TranspositionTable::new(DEFAULT_TABLE_SIZE),
+            book: None,
+        }
+    }
+
+    /// 探索の前に引く定石を設定します。
+    /// 
+    pub fn set_book(&mut self, book: Book) {
+        self.book = Some(book);
+    }
+
+    /// 設定されている定石を返します。対局の結果から学習させる場合に使います。
+    /// 
+    pub fn book_mut(&mut self) -> Option<&mut Book> {
+        self.book.as_mut()
+    }
+
+    /// 探索に使うスレッド数を設定します。0を指定した場合は1になります。
+    /// 
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// 置換表の件数を設定します。保存済みの局面は消えます。
+    /// 
+    pub fn set_table_size(&mut self, size: usize) {
+        self.table = TranspositionTable::new(size);
+    }
+
+    /// 置換表に保存した局面を全て消します。
+    /// 
+    pub fn clear_table(&mut self) {
+        self.table.clear();
+    }
+
+    /// 完全読みに切り替える空きマスの数を設定します。0を指定すると完全読みをしません。
+    /// 
+    pub fn set_endgame_empties(&mut self, endgame_empties: usize) {
+        self.endgame_empties = endgame_empties;
+    }
+
+    /// `piece_type`の手番で盤面を探索し、最善手を返します。
+    /// 
+    pub fn search(&mut self, board: &Board, piece_type: PieceType) -> SearchResult {
+        if let Some(result) = self.book_move(board, piece_type) {
+            return result;
+        }
+        if board.empty_count() <= self.endgame_empties {
+            return self.solve_endgame(board, piece_type);
+        }
+
+        self.search_depth(board, piece_type, self.depth, None).0
+    }
+
+    /// `time_control`の制限の中で深さを1ずつ増やしながら探索し、最後に読み終えた深さの結果を返します。
+    /// 
+    /// 時間を使い切ると読んでいる途中の深さは捨てます。深さ1は時間に関わらず必ず読み終えます。
+    /// 返却される`nodes`は全ての深さで探索したノード数の合計です。
+    /// 
+    pub fn think(&mut self, board: &Board, piece_type: PieceType, time_control: TimeControl) -> SearchResult {
+        let start = Instant::now();
+        if let Some(result) = self.book_move(board, piece_type) {
+            return result;
+        }
+        let empties = board.empty_count();
+        if empties <= self.endgame_empties {
+            return self.solve_endgame(board, piece_type);
+        }
+
+        let allocation = time_control.allocate(empties);
+        let max_depth = time_control.max_depth().unwrap_or(empties).max(1);
+
+        let mut result = None;
+        let mut nodes = 0;
+        for depth in 1..=max_depth {
+            let deadline = if depth == 1 { None } else { allocation.map(|allocation| start + allocation) };
+            let (iteration, aborted) = self.search_depth(board, piece_type, depth, deadline);
+            nodes += iteration.nodes;
+            if aborted {
+                break;
+            }
+            result = Some(iteration);
+
+            // 次の深さは今の深さより時間がかかるため、半分を過ぎていれば始めない
+            if allocation.is_some_and(|allocation| start.elapsed() * 2 > allocation) {
+                break;
+            }
+        }
+
+        let mut result = result.expect("depth 1 always completes");
+        result.nodes = nodes;
+        result
+    }
+
+    // 時間切れで打ち切った場合は2番目の値がtrueになる
+    fn search_depth(&self, board: &Board, piece_type: PieceType, depth: usize, deadline: Option<Instant>) -> (SearchResult, bool) {
+        let stop = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            // 補助スレッドは半数を1手深く読ませ、主スレッドと異なる局面を置換表に残させる
+            let helpers = (1..self.threads)
+                .map(|id| {
+                    let mut board = board.clone();
+                    let mut worker = Worker::new(&self.evaluator, &self.table, &stop, deadline);
+                    scope.spawn(move || {
+                        worker.negamax(&mut board, piece_type, depth + id % 2, -INFINITY, INFINITY, &mut Vec::new());
+                        worker.nodes
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut board = board.clone();
+            let mut worker = Worker::new(&self.evaluator, &self.table, &stop, deadline);
+            let mut pv = Vec::new();
+            let score = worker.negamax(&mut board, piece_type, depth, -INFINITY, INFINITY, &mut pv);
+
+            stop.store(true, Ordering::Relaxed);
+            let helper_nodes = helpers.into_iter()
+                .map(|helper| helper.join().expect("search thread panicked"))
+                .sum::<u64>();
+
+            let result = SearchResult {
+                best_move: match pv.first() {
+                    Some(MoveType::Put(point)) => Some(*point),
+                    _ => None,
+                },
+                score,
+                // 読み切った終局の評価値は石差が確定している
+                score_type: if score.abs() >= SCORE_WIN { ScoreType::Exact } else { ScoreType::Evaluation },
+                pv,
+                nodes: worker.nodes + helper_nodes,
+                depth,
+            };
+            (result, worker.aborted)
+        })
+    }
+
+    // 定石の手は深さ0、評価値は定石の石差として返す。探索の評価値とは単位が違うため種類で区別する
+    fn book_move(&mut self, board: &Board, piece_type: PieceType) -> Option<SearchResult> {
+        let book_move = self.book.as_mut()?.choose(board, piece_type)?;
+
+        Some(SearchResult {
+            best_move: Some(book_move.point),
+            score: book_move.score,
+            score_type: ScoreType::Book,
+            pv: vec![MoveType::Put(book_move.point)],
+            nodes: 0,
+            depth: 0,
+        })
+    }
+
+    fn solve_endgame(&self, board: &Board, piece_type: PieceType) -> SearchResult {
+        let result = EndgameSolver::new().solve(board, piece_type);
+
+        SearchResult {
+            best_move: result.best_move,
+            score: result.score + result.score.signum() * SCORE_WIN,
+            score_type: ScoreType::Exact,
+            pv: vec![result.best_move.map_or(MoveType::Pass, MoveType::Put)],
+            nodes: result.nodes,
+            depth: board.empty_count(),
+        }
+    }
+}
+
+impl<'a, E: Evaluator> Worker<'a, E> {
+    fn new(evaluator: &'a E, table: &'a TranspositionTable, stop: &'a AtomicBool, deadline: Option<Instant>) -> Worker<'a, E> {
+        Worker {
+            evaluator,
+            table,
+            stop,
+            deadline,
+            nodes: 0,
+            aborted: false,
+        }
+    }
+
+    fn negamax(&mut self, board: &mut Board, piece_type: PieceType, depth: usize, mut alpha: i32, beta: i32, pv: &mut Vec<MoveType>) -> i32 {
+        self.nodes += 1;
+        pv.clear();
+
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.stop.store(true, Ordering::Relaxed);
+        }
+        if self.aborted || self.stop.load(Ordering::Relaxed) {
+            self.aborted = true;
+            return 0;
+        }
+
+        let moves = board.legal_moves(piece_type);
+
+        if moves.is_empty() {
+            // 両者とも打てなければ終局
+            if board.is_skip(piece_type.change()) {
+                return terminal_score(board, piece_type);
+            }
+
+            // パスは深さを消費しない
+            let mut child_pv = Vec::new();
+            let score = -self.negamax(board, piece_type.change(), depth, -beta, -alpha, &mut child_pv);
+            if self.aborted {
+                return 0;
+            }
+            pv.push(MoveType::Pass);
+            pv.extend(child_pv);
+            return score;
+        }
+
+        if depth == 0 {
+            return self.evaluator.evaluate(board, piece_type);
+        }
+
+        // 回転や反転で同じになる局面は同じ件を使う。最善手は変換した盤面での位置で保存する
+        let (x_size, y_size) = (board.x_size(), board.y_size());
+        let (key, symmetry) = board.canonical_key(piece_type);
+        let entry = self.table.probe(key);
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth) {
+            // 窓の中に入る正確な値で打ち切ると読み筋が途切れるため、窓の外の値だけで打ち切る
+            let cutoff = match entry.bound {
+                Bound::Exact => entry.score <= alpha || entry.score >= beta,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                return entry.score;
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+
+        let table_move = entry
+            .and_then(|entry| entry.best_move)
+            .map(|point| symmetry.inverse().apply(&point, x_size, y_size));
+        for point in order_moves(board, piece_type, moves, table_move) {
+            let undo = board.make_move(piece_type, point);
+            let score = -self.negamax(board, piece_type.change(), depth - 1, -beta, -alpha, &mut child_pv);
+            board.unmake_move(undo);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(point);
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(MoveType::Put(point));
+                    pv.extend(child_pv.iter().copied());
+                }
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        let best_move = best_move.map(|point| symmetry.apply(&point, x_size, y_size));
+        self.table.store(Entry { key, depth, score: best, bound, best_move });
+
+        best
+    }
+}
+
+impl<E: Evaluator + Sync> Player for Search<E> {
+    fn choose_move(&mut self, board: &Board, piece_type: PieceType) -> Option<Point> {
+        self.search(board, piece_type).best_move
+    }
+}
+
+/// 終局した盤面の`piece_type`から見た評価値を返します。
+/// 
+pub fn terminal_score(board: &Board, piece_type: PieceType) -> i32 {
+    let score = board.final_score(piece_type);
+    score + score.signum() * SCORE_WIN
+}
+
+// 置換表の最善手、隅の順に優先し、残りは相手の打てる場所が少なくなる手から順に並べる
+fn order_moves(board: &mut Board, piece_type: PieceType, moves: Vec<Point>, table_move: Option<Point>) -> Vec<Point> {
+    let (x_size, y_size) = (board.x_size(), board.y_size());
+    let mut ordered = moves.into_iter()
+        .map(|point| {
+            let undo = board.make_move(piece_type, point);
+            let mobility = board.legal_move_count(piece_type.change());
+            board.unmake_move(undo);
+
+            let is_corner = (point.x == 1 || point.x == x_size) && (point.y == 1 || point.y == y_size);
+            (point, Some(point) != table_move, !is_corner, mobility)
+        })
+        .collect::<Vec<_>>();
+    ordered.sort_by_key(|&(_, not_table_move, not_corner, mobility)| (not_table_move, not_corner, mobility));
+
+    ordered.into_iter().map(|(point, _, _, _)| point).collect()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symmetry::Symmetry;
+
+    #[test]
+    fn test_search() {
+        let board = Board::new(8, 8);
+        let result = Search::new(4).search(&board, PieceType::Black);
+
+        assert!(board.can_put_piece(PieceType::Black, &result.best_move.unwrap()));
+        assert_eq!(4, result.pv.len());
+        assert!(result.nodes > 0);
+        assert_eq!(result, Search::new(4).search(&board, PieceType::Black));
+    }
+
+    #[test]
+    fn test_search_wipe_out() {
+        //  |0|1|2|3|4|5|6|7|8|9|
+        // 0|×|×|×|×|×|×|×|×|×|×|
+        // 1|×|●| | | | | | | |×|
+        // 2|×| |○| | | | | | |×|
+        // 3|×| |●|○| | | | | |×|
+        // 4|×| | | | | | | | |×|
+        // 5|×| | | | | | | | |×|
+        // 6|×| | | | | | | | |×|
+        // 7|×| | | | | | | | |×|
+        // 8|×| | | | | | | | |×|
+        // 9|×|×|×|×|×|×|×|×|×|×|
+        let position = "X-------".to_string() + "-O------" + "-XO-----" + &"-".repeat(40) + " X";
+        let (board, turn) = Board::from_position_str(&position, 8, 8).ok().unwrap();
+
+        // (4,4) で白が全て無くなる
+        let result = Search::new(3).search(&board, turn);
+        assert_eq!(Some(Point::new(4, 4)), result.best_move);
+        assert_eq!(SCORE_WIN + 64, result.score);
+    }
+
+    #[test]
+    fn test_search_wipe_out_score_type() {
+        // 完全読みでなくても、終局まで読み切った評価値は確定値として扱う
+        let position = "X-------".to_string() + "-O------" + "-XO-----" + &"-".repeat(40) + " X";
+        let (board, turn) = Board::from_position_str(&position, 8, 8).unwrap();
+
+        let result = Search::new(3).search(&board, turn);
+        assert_eq!(SCORE_WIN + 64, result.score);
+        assert_eq!(ScoreType::Exact, result.score_type);
+    }
+
+    #[test]
+    fn test_search_pass() {
+        let board = Board::new(4, 4)
+            .put_piece(PieceType::Black, Point::new(2, 1))
+            .put_piece(PieceType::White, Point::new(3, 1))
+            .put_piece(PieceType::Black, Point::new(4, 3))
+            .put_piece(PieceType::White, Point::new(1, 1));
+
+        let result = Search::new(2).search(&board, PieceType::Black);
+        assert_eq!(None, result.best_move);
+        assert_eq!(MoveType::Pass, result.pv[0]);
+    }
+
+    #[test]
+    fn test_search_endgame() {
+        let position = "X".repeat(62) + "O- X";
+        let (board, turn) = Board::from_position_str(&position, 8, 8).unwrap();
+
+        // 空きマスが少ないので完全読みになる
+        let result = Search::new(1).search(&board, turn);
+        assert_eq!(Some(Point::new(8, 8)), result.best_move);
+        assert_eq!(SCORE_WIN + 64, result.score);
+        assert_eq!(ScoreType::Exact, result.score_type);
+        assert_eq!(vec![MoveType::Put(Point::new(8, 8))], result.pv);
+    }
+
+    #[test]
+    fn test_search_table() {
+        let board = Board::new(8, 8);
+        let mut search = Search::new(5);
+        search.set_endgame_empties(0);
+        let first = search.search(&board, PieceType::Black);
+
+        // 置換表が残っていれば同じ結果をより少ないノード数で得られる
+        let second = search.search(&board, PieceType::Black);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.best_move, second.best_move);
+        assert!(second.nodes < first.nodes);
+
+        search.clear_table();
+        assert_eq!(first, search.search(&board, PieceType::Black));
+    }
+
+    #[test]
+    fn test_search_table_symmetry() {
+        let board = Board::new(8, 8).put_piece(PieceType::Black, Point::new(4, 3));
+        let mut search = Search::new(4);
+        search.set_endgame_empties(0);
+        let first = search.search(&board, PieceType::White);
+
+        // 反転した局面は同じ件を引き、最善手も反転した位置に戻せる
+        let mirrored = board.transform(Symmetry::FlipDiagonal);
+        let (key, symmetry) = mirrored.canonical_key(PieceType::White);
+        let entry = search.table.probe(key).unwrap();
+        assert_eq!(4, entry.depth);
+        assert_eq!(first.score, entry.score);
+        let best_move = symmetry.inverse().apply(&entry.best_move.unwrap(), 8, 8);
+        assert_eq!(Symmetry::FlipDiagonal.apply(&first.best_move.unwrap(), 8, 8), best_move);
+
+        let second = search.search(&mirrored, PieceType::White);
+        assert_eq!(first.score, second.score);
+        assert!(second.nodes < first.nodes);
+    }
+
+    #[test]
+    fn test_think_depth() {
+        let board = Board::new(8, 8);
+        let result = Search::new(1).think(&board, PieceType::Black, TimeControl::Depth(4));
+        let expected = Search::new(4).search(&board, PieceType::Black);
+
+        assert_eq!(4, result.depth);
+        assert_eq!(expected.score, result.score);
+        assert_eq!(expected.best_move, result.best_move);
+    }
+
+    #[test]
+    fn test_think_time() {
+        let board = Board::new(8, 8);
+        let time = std::time::Duration::from_millis(50);
+        let start = Instant::now();
+        let result = Search::new(1).think(&board, PieceType::Black, TimeControl::MoveTime(time));
+
+        // 時間内に打ち切り、読み終えた深さの手を返す
+        assert!(start.elapsed() < time * 4);
+        assert!(result.depth >= 1);
+        assert!(board.can_put_piece(PieceType::Black, &result.best_move.unwrap()));
+    }
+
+    #[test]
+    fn test_search_threads() {
+        let board = Board::new(8, 8)
+            .put_piece(PieceType::Black, Point::new(4, 3))
+            .put_piece(PieceType::White, Point::new(3, 3));
+
+        // 1スレッドなら何度探索しても同じ結果になる
+        let mut search = Search::new(5);
+        search.set_threads(1);
+        assert_eq!(search.search(&board, PieceType::Black), Search::new(5).search(&board, PieceType::Black));
+
+        let mut search = Search::new(5);
+        search.set_threads(4);
+        let result = search.search(&board, PieceType::Black);
+        assert_eq!(5, result.depth);
+        assert!(board.can_put_piece(PieceType::Black, &result.best_move.unwrap()));
+
+        let result = search.think(&board, PieceType::Black, TimeControl::MoveTime(std::time::Duration::from_millis(50)));
+        assert!(result.depth >= 1);
+        assert!(board.can_put_piece(PieceType::Black, &result.best_move.unwrap()));
+    }
+
+    #[test]
+    fn test_search_book() {
+        let mut search = Search::new(3);
+        search.set_book(Book::parse("f5f6 +2\nf5d6 -4", 8, 8).unwrap());
+
+        // 定石の局面では探索しない
+        let board = Board::new(8, 8).put_piece(PieceType::Black, Point::new(6, 5));
+        let result = search.think(&board, PieceType::White, TimeControl::Depth(3));
+        assert_eq!(Some(Point::new(4, 6)), result.best_move);
+        assert_eq!(0, result.nodes);
+        assert_eq!((ScoreType::Book, 4), (result.score_type, result.score));
+
+        // 定石を外れると探索する
+        let board = board.put_piece(PieceType::White, Point::new(4, 6));
+        let result = search.search(&board, PieceType::Black);
+        assert_eq!(3, result.depth);
+        assert!(result.nodes > 0);
+        assert_eq!(ScoreType::Evaluation, result.score_type);
+    }
+}
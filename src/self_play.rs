@@ -0,0 +1,434 @@
+//! 自己対局による学習データの生成
+//! 
+//! 2つのプレイヤーを対局させ、打った全ての局面を手番・評価値・最終石差と共に記録します。
+//! 評価値はプレイヤーとは別の`ScoreSource`で求めるため、どのプレイヤー同士の対局でも同じ基準で記録できます。
+//! 序盤の数手を無作為に打つことで対局ごとに異なる局面を集めます。
+//! 
+
+use crate::board::{Board, ParseBoardError};
+use crate::evaluator::Evaluator;
+use crate::pattern_trainer::Sample;
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use crate::point::Point;
+use crate::random_player::RandomPlayer;
+use crate::score_type::ScoreType;
+use crate::search::{Search, SearchResult, SCORE_WIN};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+// バイナリ形式の先頭と版
+const MAGIC: &[u8; 4] = b"RVSP";
+const VERSION: u16 = 2;
+
+/// # Record 構造体
+/// 
+/// 自己対局で探索した1局面の記録
+/// 
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Record {
+    /// 局面
+    pub board: Board,
+    /// 手番
+    pub turn: PieceType,
+    /// 手番から見た評価値。種類が`Exact`と`Book`の場合は石差です
+    pub score: i32,
+    /// 評価値の種類。種類によって単位が違います
+    pub score_type: ScoreType,
+    /// 手番から見た最終石差
+    pub result: i32,
+}
+
+impl Record {
+    /// `PatternTrainer`で学習に使う形に変換します。
+    /// 
+    pub fn to_sample(&self) -> Sample {
+        let score = if self.turn == PieceType::Black { self.result } else { -self.result };
+        Sample { board: self.board.clone(), turn: self.turn, score }
+    }
+}
+
+/// 自己対局で記録する局面の評価値を求めるトレイト
+/// 
+pub trait ScoreSource {
+    /// `piece_type`の手番から見た評価値と、その種類を返します。
+    /// 種類が`Exact`と`Book`の場合は石差を返します。
+    fn score(&mut self, board: &Board, piece_type: PieceType) -> (i32, ScoreType);
+}
+
+impl<E: Evaluator + Sync> ScoreSource for Search<E> {
+    fn score(&mut self, board: &Board, piece_type: PieceType) -> (i32, ScoreType) {
+        record_score(&self.search(board, piece_type))
+    }
+}
+
+/// 探索の結果を記録する評価値と種類に変換します。
+/// 
+/// 確定した評価値は`SCORE_WIN`を除いて石差に直し、評価関数の値と桁が混ざらないようにします。
+/// 
+pub fn record_score(result: &SearchResult) -> (i32, ScoreType) {
+    let score = match result.score_type {
+        ScoreType::Exact => result.score - result.score.signum() * SCORE_WIN,
+        _ => result.score,
+    };
+    (score, result.score_type)
+}
+
+/// # SelfPlay 構造体
+/// 
+/// 自己対局の設定
+/// 
+/// 対局ごとに先手と後手を入れ替え、最初の`random_moves`手は`seed`と対局の番号から決まる無作為な手を打ちます。
+/// 新しく生成したプレイヤーと`ScoreSource`から始めれば、同じシードと設定で同じ記録になります。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SelfPlay {
+    /// 盤面の横の大きさ
+    pub x_size: usize,
+    /// 盤面の縦の大きさ
+    pub y_size: usize,
+    /// 対局数
+    pub games: usize,
+    /// 序盤に無作為に打つ手数
+    pub random_moves: usize,
+    /// 乱数のシード
+    pub seed: u64,
+}
+
+impl SelfPlay {
+    /// 8x8の盤面で`games`局対局する設定を生成します。
+    /// 
+    pub fn new(games: usize, random_moves: usize, seed: u64) -> SelfPlay {
+        SelfPlay {
+            x_size: 8,
+            y_size: 8,
+            games,
+            random_moves,
+            seed,
+        }
+    }
+
+    /// 全ての対局を行い、記録を返します。偶数番目の対局は`first`が黒を持ちます。
+    /// 
+    pub fn run(&self, first: &mut dyn Player, second: &mut dyn Player, scorer: &mut dyn ScoreSource) -> Vec<Record> {
+        (0..self.games)
+            .flat_map(|index| self.play_game(index, first, second, scorer))
+            .collect()
+    }
+
+    /// `index`番目の対局を行い、記録を返します。
+    /// 
+    pub fn play_game(&self, index: usize, first: &mut dyn Player, second: &mut dyn Player, scorer: &mut dyn ScoreSource) -> Vec<Record> {
+        let mut random = RandomPlayer::new(self.seed ^ (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let mut board = Board::new(self.x_size, self.y_size);
+        let mut turn = PieceType::Black;
+        let mut plies = 0;
+        // (局面, 手番, 評価値, 評価値の種類)
+        let mut positions = Vec::new();
+
+        while !board.is_end() {
+            if board.is_skip(turn) {
+                turn = turn.change();
+                continue;
+            }
+
+            // 評価値はプレイヤーが手を選んだ後に求めるため、`scorer`はプレイヤーの探索結果を使い回せる
+            let point = if plies < self.random_moves {
+                random.choose_move(&board, turn)
+            } else {
+                let point = if (turn == PieceType::Black) == index.is_multiple_of(2) {
+                    first.choose_move(&board, turn)
+                } else {
+                    second.choose_move(&board, turn)
+                };
+                let (score, score_type) = scorer.score(&board, turn);
+                positions.push((board.clone(), turn, score, score_type));
+                point
+            };
+
+            // 打てる場所がある手番なので必ず手が選ばれる
+            if let Some(point) = point {
+                board.make_move(turn, point);
+            }
+            turn = turn.change();
+            plies += 1;
+        }
+
+        let black_result = board.final_score(PieceType::Black);
+        positions.into_iter()
+            .map(|(board, turn, score, score_type)| Record {
+                board,
+                turn,
+                score,
+                score_type,
+                result: if turn == PieceType::Black { black_result } else { -black_result },
+            })
+            .collect()
+    }
+}
+
+/// 記録を`position,turn,score,score_type,result`の見出しを付けたCSVで書き出します。
+/// `position`は`Board::to_position_str`の盤面部分、`turn`は`X`か`O`、
+/// `score_type`は評価値の種類で`evaluation`・`exact`・`book`のいずれかです。
+/// 
+pub fn write_csv<W: Write>(records: &[Record], writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "position,turn,score,score_type,result")?;
+    for record in records {
+        let position = record.board.to_position_str(record.turn);
+        let (cells, turn) = position.split_once(' ').unwrap_or((&position, ""));
+        let score_type = match record.score_type {
+            ScoreType::Evaluation => "evaluation",
+            ScoreType::Exact => "exact",
+            ScoreType::Book => "book",
+        };
+        writeln!(writer, "{},{},{},{},{}", cells, turn, record.score, score_type, record.result)?;
+    }
+    Ok(())
+}
+
+/// 記録をバイナリ形式で書き出します。
+/// 
+/// 先頭の`RVSP`と版(u16)、盤面の横と縦の大きさ(u8)に続けて、1局面ごとに
+/// 盤面(1マス2ビット、空き0・黒1・白2を左上から下位ビットに詰める)、手番(u8、黒0・白1)、
+/// 評価値(i32)、評価値の種類(u8、評価関数0・読み切り1・定石2)、最終石差(i16)を並べます。
+/// 数値は全てリトルエンディアンです。8x8の盤面は1局面24バイトです。
+/// 
+/// 盤面の大きさが255を超える場合と、最終石差がi16に収まらない場合は書き出さずにエラーを返します。
+/// 
+pub fn write_binary<W: Write>(records: &[Record], x_size: usize, y_size: usize, writer: &mut W) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let sizes = u8::try_from(x_size).and_then(|x| u8::try_from(y_size).map(|y| [x, y]))
+        .map_err(|_| invalid(format!("盤面の大きさが大きすぎます : {}x{}", x_size, y_size)))?;
+    let results = records.iter()
+        .map(|record| i16::try_from(record.result).map_err(|_| invalid(format!("最終石差が大きすぎます : {}", record.result))))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&sizes)?;
+
+    for (record, result) in records.iter().zip(results) {
+        let mut cells = vec![0_u8; cell_bytes(x_size, y_size)];
+        for (index, point) in points(x_size, y_size).enumerate() {
+            let value = match record.board.piece_at(&point) {
+                Some(PieceType::Black) => 1,
+                Some(PieceType::White) => 2,
+                None => 0,
+            };
+            cells[index / 4] |= value << (index % 4 * 2);
+        }
+        let score_type = match record.score_type {
+            ScoreType::Evaluation => 0,
+            ScoreType::Exact => 1,
+            ScoreType::Book => 2,
+        };
+        writer.write_all(&cells)?;
+        writer.write_all(&[if record.turn == PieceType::Black { 0 } else { 1 }])?;
+        writer.write_all(&record.score.to_le_bytes())?;
+        writer.write_all(&[score_type])?;
+        writer.write_all(&result.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// `write_binary`で書き出した記録を読み込みます。
+/// 
+pub fn read_binary(bytes: &[u8]) -> Result<Vec<Record>, ReadRecordError> {
+    if bytes.len() < 8 || &bytes[..4] != MAGIC {
+        return Err(ReadRecordError::InvalidMagic);
+    }
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if version != VERSION {
+        return Err(ReadRecordError::UnsupportedVersion(version));
+    }
+    let (x_size, y_size) = (bytes[6] as usize, bytes[7] as usize);
+    if x_size == 0 || y_size == 0 {
+        return Err(ReadRecordError::InvalidSize(x_size, y_size));
+    }
+
+    let record_size = cell_bytes(x_size, y_size) + 8;
+    let body = &bytes[8..];
+    if !body.len().is_multiple_of(record_size) {
+        return Err(ReadRecordError::InvalidLength(bytes.len()));
+    }
+
+    body.chunks_exact(record_size)
+        .map(|chunk| {
+            let (cells, rest) = chunk.split_at(cell_bytes(x_size, y_size));
+            let mut position = String::new();
+            for index in 0..x_size * y_size {
+                position.push(match cells[index / 4] >> (index % 4 * 2) & 0b11 {
+                    0 => '-',
+                    1 => 'X',
+                    2 => 'O',
+                    _ => return Err(ReadRecordError::InvalidCell(index)),
+                });
+            }
+            let turn = match rest[0] {
+                0 => PieceType::Black,
+                1 => PieceType::White,
+                value => return Err(ReadRecordError::InvalidTurn(value)),
+            };
+            let score_type = match rest[5] {
+                0 => ScoreType::Evaluation,
+                1 => ScoreType::Exact,
+                2 => ScoreType::Book,
+                value => return Err(ReadRecordError::InvalidScoreType(value)),
+            };
+            position.push_str(if turn == PieceType::Black { " X" } else { " O" });
+            let (board, _) = Board::from_position_str(&position, x_size, y_size).map_err(ReadRecordError::InvalidPosition)?;
+
+            Ok(Record {
+                board,
+                turn,
+                score: i32::from_le_bytes([rest[1], rest[2], rest[3], rest[4]]),
+                score_type,
+                result: i16::from_le_bytes([rest[6], rest[7]]) as i32,
+            })
+        })
+        .collect()
+}
+
+/// 記録を読み込めなかった理由
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ReadRecordError {
+    /// 自己対局の記録ではない
+    InvalidMagic,
+    /// 対応していない版
+    UnsupportedVersion(u16),
+    /// 盤面の大きさが不正
+    InvalidSize(usize, usize),
+    /// 局面の途中で終わっている。値は全体のバイト数
+    InvalidLength(usize),
+    /// マスの値が不正。値は何番目のマスか
+    InvalidCell(usize),
+    /// 手番の値が不正
+    InvalidTurn(u8),
+    /// 評価値の種類の値が不正
+    InvalidScoreType(u8),
+    /// 局面が不正
+    InvalidPosition(ParseBoardError),
+}
+
+impl fmt::Display for ReadRecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadRecordError::InvalidMagic => write!(f, "自己対局の記録ではありません"),
+            ReadRecordError::UnsupportedVersion(version) => write!(f, "対応していない版です : {}", version),
+            ReadRecordError::InvalidSize(x_size, y_size) => write!(f, "盤面の大きさが不正です : {}x{}", x_size, y_size),
+            ReadRecordError::InvalidLength(length) => write!(f, "長さが不正です : {}バイト", length),
+            ReadRecordError::InvalidCell(index) => write!(f, "{}番目のマスが不正です", index),
+            ReadRecordError::InvalidTurn(value) => write!(f, "手番が不正です : {}", value),
+            ReadRecordError::InvalidScoreType(value) => write!(f, "評価値の種類が不正です : {}", value),
+            ReadRecordError::InvalidPosition(error) => write!(f, "局面が不正です : {}", error),
+        }
+    }
+}
+
+impl Error for ReadRecordError {}
+
+fn cell_bytes(x_size: usize, y_size: usize) -> usize {
+    (x_size * y_size).div_ceil(4)
+}
+
+// 左上から行ごとに盤内のマスを並べる
+fn points(x_size: usize, y_size: usize) -> impl Iterator<Item = Point> {
+    (1..=y_size).flat_map(move |y| (1..=x_size).map(move |x| Point::new(x, y)))
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greedy_player::GreedyPlayer;
+
+    // (先手, 後手, 評価値を求める探索)
+    fn engines() -> (Search, Search, Search) {
+        let mut first = Search::new(2);
+        let mut second = Search::new(1);
+        let mut scorer = Search::new(1);
+        first.set_endgame_empties(4);
+        second.set_endgame_empties(4);
+        scorer.set_endgame_empties(4);
+        (first, second, scorer)
+    }
+
+    #[test]
+    fn test_run() {
+        let self_play = SelfPlay::new(2, 4, 7);
+        let (mut first, mut second, mut scorer) = engines();
+        let records = self_play.run(&mut first, &mut second, &mut scorer);
+
+        // 同じシードなら同じ記録になる
+        let (mut first, mut second, mut scorer) = engines();
+        assert!(records == self_play.run(&mut first, &mut second, &mut scorer));
+
+        // 無作為に打った4手の後の局面から記録する
+        assert_eq!(56, records[0].board.empty_count());
+        assert!(records.iter().all(|record| !record.board.is_skip(record.turn)));
+
+        // 最終石差は手番から見た値
+        let last = records.last().unwrap();
+        let next = records.iter().rev().find(|record| record.turn != last.turn).unwrap();
+        assert_eq!(last.result, -next.result);
+        assert_eq!(last.result, last.to_sample().score * if last.turn == PieceType::Black { 1 } else { -1 });
+
+        // 読み切った局面は評価関数の値と区別する
+        for record in &records {
+            let expected = if record.board.empty_count() <= 4 { ScoreType::Exact } else { ScoreType::Evaluation };
+            assert_eq!(expected, record.score_type);
+            // 終盤は両者とも読み切るので、確定した評価値は最終石差と一致する
+            if record.score_type == ScoreType::Exact {
+                assert_eq!(record.result, record.score);
+            }
+        }
+
+        let (mut first, mut second, mut scorer) = engines();
+        assert!(records != SelfPlay::new(2, 4, 8).run(&mut first, &mut second, &mut scorer));
+    }
+
+    #[test]
+    fn test_run_players() {
+        // 探索以外のプレイヤー同士でも同じ基準で評価値を記録できる
+        let self_play = SelfPlay::new(2, 2, 3);
+        let (_, _, mut scorer) = engines();
+        let records = self_play.run(&mut GreedyPlayer::new(), &mut RandomPlayer::new(5), &mut scorer);
+        assert_eq!(58, records[0].board.empty_count());
+        for record in &records {
+            assert_eq!((record.score, record.score_type), scorer.score(&record.board, record.turn));
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let self_play = SelfPlay { x_size: 6, y_size: 6, ..SelfPlay::new(1, 2, 1) };
+        let (mut first, mut second, mut scorer) = engines();
+        let records = self_play.run(&mut first, &mut second, &mut scorer);
+
+        let mut csv = Vec::new();
+        write_csv(&records, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(records.len() + 1, csv.lines().count());
+        assert!(csv.lines().nth(1).unwrap().starts_with(&records[0].board.to_position_str(records[0].turn).replace(' ', ",")));
+        assert!(csv.lines().last().unwrap().contains(",exact,"));
+
+        let mut binary = Vec::new();
+        write_binary(&records, 6, 6, &mut binary).unwrap();
+        assert_eq!(8 + records.len() * (9 + 8), binary.len());
+        assert!(records == read_binary(&binary).unwrap());
+
+        assert_eq!(Err(ReadRecordError::InvalidMagic), read_binary(b"CSV").map(|_| ()));
+        assert_eq!(Err(ReadRecordError::InvalidLength(binary.len() - 1)), read_binary(&binary[..binary.len() - 1]).map(|_| ()));
+        let empty = [&MAGIC[..], &VERSION.to_le_bytes(), &[0, 0], &[0; 8]].concat();
+        assert_eq!(Err(ReadRecordError::InvalidSize(0, 0)), read_binary(&empty).map(|_| ()));
+
+        // 収まらない値は切り詰めずにエラーにする
+        assert!(write_binary(&records, 256, 6, &mut Vec::new()).is_err());
+        let overflow = vec![Record { result: 40_000, ..records[0].clone() }];
+        assert!(write_binary(&overflow, 6, 6, &mut Vec::new()).is_err());
+    }
+}
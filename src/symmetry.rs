@@ -0,0 +1,105 @@
+use crate::point::Point;
+
+/// 盤面の対称変換
+/// 
+/// 90度回転と対角線での反転は縦横の大きさが同じ盤面でのみ使えます。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    FlipDiagonal,
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    /// 全ての対称変換
+    /// 
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// `x_size`x`y_size`の盤面で使える対称変換を返します。
+    /// 
+    pub fn all_for(x_size: usize, y_size: usize) -> impl Iterator<Item = Symmetry> {
+        Symmetry::ALL.into_iter().filter(move |symmetry| symmetry.is_valid_for(x_size, y_size))
+    }
+
+    /// `x_size`x`y_size`の盤面で使える場合にtrueを返します。
+    /// 
+    pub fn is_valid_for(&self, x_size: usize, y_size: usize) -> bool {
+        match self {
+            Symmetry::Rotate90 | Symmetry::Rotate270 | Symmetry::FlipDiagonal | Symmetry::FlipAntiDiagonal => x_size == y_size,
+            _ => true,
+        }
+    }
+
+    /// 元に戻す対称変換を返します。
+    /// 
+    pub fn inverse(&self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            symmetry => *symmetry,
+        }
+    }
+
+    /// `x_size`x`y_size`の盤面の`point`を変換した位置を返します。
+    /// 
+    pub fn apply(&self, point: &Point, x_size: usize, y_size: usize) -> Point {
+        // 1始まりの座標を盤面の反対側に写す
+        let (x, y) = (point.x, point.y);
+        let (rx, ry) = (x_size + 1 - x, y_size + 1 - y);
+
+        match self {
+            Symmetry::Identity => Point::new(x, y),
+            Symmetry::Rotate90 => Point::new(ry, x),
+            Symmetry::Rotate180 => Point::new(rx, ry),
+            Symmetry::Rotate270 => Point::new(y, rx),
+            Symmetry::FlipHorizontal => Point::new(rx, y),
+            Symmetry::FlipVertical => Point::new(x, ry),
+            Symmetry::FlipDiagonal => Point::new(y, x),
+            Symmetry::FlipAntiDiagonal => Point::new(ry, rx),
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_and_inverse() {
+        for symmetry in Symmetry::ALL {
+            for x in 1..=8 {
+                for y in 1..=8 {
+                    let point = Point::new(x, y);
+                    let transformed = symmetry.apply(&point, 8, 8);
+                    assert_eq!(point, symmetry.inverse().apply(&transformed, 8, 8));
+                }
+            }
+        }
+
+        assert_eq!(Point::new(8, 1), Symmetry::Rotate90.apply(&Point::new(1, 1), 8, 8));
+        assert_eq!(Point::new(6, 3), Symmetry::FlipHorizontal.apply(&Point::new(1, 3), 6, 4));
+    }
+
+    #[test]
+    fn test_all_for() {
+        assert_eq!(8, Symmetry::all_for(8, 8).count());
+        assert_eq!(4, Symmetry::all_for(6, 4).count());
+    }
+}
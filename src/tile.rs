@@ -1,59 +1,93 @@
+use crate::bitboard::{self, Bitboard};
 use crate::point::Point;
 use crate::piece_type::PieceType;
 use crate::tile_type::TileType;
 
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 
+#[derive(Clone, PartialEq, Eq)]
 pub struct Tile {
-    tiles: HashMap<Point, TileType>,
+    tiles: Tiles,
     x_size: usize,
     y_size: usize,
 }
 
-pub struct TileIter<'a> {
-    x: usize,
-    y: usize,
-    tile: &'a Tile
+// 8x8はビットボード、それ以外の大きさはHashMapで盤面を保持する
+#[derive(Clone, PartialEq, Eq)]
+enum Tiles {
+    Bitboard(Bitboard),
+    Map(HashMap<Point, TileType>),
 }
 
-pub struct PointIter {
+pub struct TileIter<'a> {
     x: usize,
     y: usize,
-    x_size: usize,
-    y_size: usize,
+    tile: &'a Tile
 }
 
 impl Tile {
     pub fn new(x_size: usize, y_size: usize) -> Tile {
+        if x_size == bitboard::SIZE && y_size == bitboard::SIZE {
+            Tile {
+                tiles: Tiles::Bitboard(Bitboard::new()),
+                x_size,
+                y_size,
+            }
+        } else {
+            Tile::new_general(x_size, y_size)
+        }
+    }
+
+    /// 盤面の大きさに関わらずHashMapで保持する`Tile`を生成します。
+    /// 
+    pub fn new_general(x_size: usize, y_size: usize) -> Tile {
         Tile {
-            tiles: Tile::initialize_tiles(x_size, y_size),
+            tiles: Tiles::Map(Tile::initialize_tiles(x_size, y_size)),
             x_size,
             y_size,
         }
     }
 
     pub fn get(&self, k: &Point) -> Option<&TileType> {
-        self.tiles.get(k)
+        match &self.tiles {
+            Tiles::Bitboard(bitboard) => bitboard.get(k),
+            Tiles::Map(tiles) => tiles.get(k),
+        }
     }
 
     pub fn set(&mut self, point: Point, tile_type: TileType) {
-        self.tiles.insert(point, tile_type);
+        match &mut self.tiles {
+            Tiles::Bitboard(bitboard) => bitboard.set(&point, tile_type),
+            Tiles::Map(tiles) => {
+                tiles.insert(point, tile_type);
+            }
+        }
     }
 
-    pub fn iter_tile(&self) -> TileIter {
-        TileIter {
-            x: 0,
-            y: 0,
-            tile: self,
+    /// ビットボードで保持している場合はビットボードを返します。
+    /// 
+    pub fn bitboard(&self) -> Option<&Bitboard> {
+        match &self.tiles {
+            Tiles::Bitboard(bitboard) => Some(bitboard),
+            Tiles::Map(_) => None,
+        }
+    }
+
+    /// ビットボードで保持している場合は変更可能なビットボードを返します。
+    /// 
+    pub fn bitboard_mut(&mut self) -> Option<&mut Bitboard> {
+        match &mut self.tiles {
+            Tiles::Bitboard(bitboard) => Some(bitboard),
+            Tiles::Map(_) => None,
         }
     }
 
-    pub fn iter_point(&self) -> PointIter {
-        PointIter {
+    pub fn iter_tile(&self) -> TileIter<'_> {
+        TileIter {
             x: 0,
             y: 0,
-            x_size: self.x_size,
-            y_size: self.y_size,
+            tile: self,
         }
     }
 
@@ -89,6 +123,18 @@ impl Tile {
     }
 }
 
+impl Hash for Tile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x_size.hash(state);
+        self.y_size.hash(state);
+        match &self.tiles {
+            Tiles::Bitboard(bitboard) => bitboard.hash(state),
+            // HashMapは順序が不定なので盤面の順に並べる
+            Tiles::Map(_) => self.iter_tile().for_each(|tile| tile.hash(state)),
+        }
+    }
+}
+
 impl<'a> Iterator for TileIter<'a> {
     type Item = &'a TileType;
 
@@ -109,24 +155,3 @@ impl<'a> Iterator for TileIter<'a> {
         self.tile.get(&point)
     }
 }
-
-impl Iterator for PointIter {
-    type Item = Point;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let point = Point::new(self.x, self.y);
-
-        if self.x_size + 2 > self.x + 1 {
-            self.x += 1;
-        } else {
-            self.x = 0;
-            if self.y_size + 2 > self.y + 1 {
-                self.y += 1;
-            } else {
-                return None;
-            }
-        }
-
-        Some(point)
-    }
-}
@@ -1,7 +1,7 @@
 use crate::piece_type::PieceType;
 use std::fmt;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TileType {
     Piece(PieceType),
     Square,
@@ -0,0 +1,73 @@
+//! 思考時間の管理
+//! 
+
+use std::time::Duration;
+
+// 通信や手の送信に使う分として持ち時間から残しておく割合
+const SAFETY_DIVISOR: u32 = 10;
+
+/// # TimeControl 列挙型
+/// 
+/// 一手を考えるための制限
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeControl {
+    /// 指定した深さまで読む。時間の制限はありません
+    Depth(usize),
+    /// 一手ごとに決まった時間だけ考える
+    MoveTime(Duration),
+    /// 対局全体の持ち時間。`increment`は一手ごとに加算される時間
+    Clock { remaining: Duration, increment: Duration },
+}
+
+impl TimeControl {
+    /// 空きマスが`empties`の局面で使える時間を返します。時間の制限が無い場合はNoneを返します。
+    /// 
+    /// 持ち時間の場合は、残りの自分の手数(空きマスの半分)で持ち時間を等分し、
+    /// 加算される時間を足します。持ち時間を使い切らないよう一割は残します。
+    /// 
+    pub fn allocate(&self, empties: usize) -> Option<Duration> {
+        match *self {
+            TimeControl::Depth(_) => None,
+            TimeControl::MoveTime(time) => Some(time),
+            TimeControl::Clock { remaining, increment } => {
+                let moves = (empties as u32).div_ceil(2).max(1);
+                let limit = remaining - remaining / SAFETY_DIVISOR;
+                Some((remaining / moves + increment).min(limit))
+            }
+        }
+    }
+
+    /// 読む深さの上限を返します。
+    /// 
+    pub fn max_depth(&self) -> Option<usize> {
+        match *self {
+            TimeControl::Depth(depth) => Some(depth),
+            _ => None,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate() {
+        assert_eq!(None, TimeControl::Depth(5).allocate(60));
+        assert_eq!(Some(Duration::from_millis(300)), TimeControl::MoveTime(Duration::from_millis(300)).allocate(60));
+
+        // 60マス空きなら30手で等分する
+        let clock = TimeControl::Clock { remaining: Duration::from_secs(60), increment: Duration::ZERO };
+        assert_eq!(Some(Duration::from_secs(2)), clock.allocate(60));
+        // 終盤ほど一手に多く使う
+        assert_eq!(Some(Duration::from_secs(12)), clock.allocate(9));
+        // 最後の一手でも一割は残す
+        assert_eq!(Some(Duration::from_secs(54)), clock.allocate(1));
+
+        let clock = TimeControl::Clock { remaining: Duration::from_secs(60), increment: Duration::from_secs(1) };
+        assert_eq!(Some(Duration::from_secs(3)), clock.allocate(60));
+    }
+}
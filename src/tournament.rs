@@ -0,0 +1,710 @@
+//! 複数のプレイヤーによる総当たり戦とスイス式トーナメント
+//! 
+//! 1回の対戦は同じ開始局面から先後を入れ替えた2局で、1局ごとに勝ち1点・引き分け0.5点です。
+//! 対局の結果は状態ファイルに1局ごとに書き出すため、中断しても続きから再開できます。
+//! 
+
+use crate::arena;
+use crate::board::{Board, ParseBoardError};
+use crate::piece_type::PieceType;
+use crate::player::Player;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// 1回の対戦の対局数と、不戦勝で得る点数
+const GAMES_PER_PAIRING: usize = 2;
+const BYE_POINTS: f64 = GAMES_PER_PAIRING as f64;
+
+/// トーナメントの形式
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// 全員と`cycles`回ずつ対戦する総当たり戦
+    RoundRobin { cycles: usize },
+    /// 得点の近い相手と`rounds`回戦うスイス式
+    Swiss { rounds: usize },
+}
+
+/// # GameRecord 構造体
+/// 
+/// 1局分の結果。プレイヤーは参加順の番号(0始まり)で表します。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GameRecord {
+    /// 回戦(1始まり)
+    pub round: usize,
+    pub black: usize,
+    pub white: usize,
+    /// 黒から見た石差
+    pub disc_difference: i32,
+}
+
+/// # Standing 構造体
+/// 
+/// 順位表の1行
+/// 
+#[derive(Clone, PartialEq, Debug)]
+pub struct Standing {
+    /// 参加順の番号
+    pub player: usize,
+    pub name: String,
+    /// 得点(不戦勝を含む)
+    pub score: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// 対戦相手の得点の合計
+    pub buchholz: f64,
+    /// 勝った相手の得点と引き分けた相手の得点の半分の合計(ゾンネボーン・ベルガー)
+    pub sonneborn_berger: f64,
+    /// 石差の合計
+    pub disc_difference: i64,
+}
+
+/// # Tournament 構造体
+/// 
+/// 対戦の組み合わせと結果を管理する構造体
+/// 
+/// 組み合わせは記録済みの結果だけから決めるため、状態ファイルから読み込んで同じ対戦を続けられます。
+/// スイス式は得点、ブッフホルツ、参加順に並べて上から同じ相手と再戦しないように組み、
+/// 参加者が奇数の回戦は不戦勝の無い最下位のプレイヤーを不戦勝(2点)にします。
+/// 
+pub struct Tournament {
+    format: Format,
+    names: Vec<String>,
+    x_size: usize,
+    y_size: usize,
+    openings: Vec<(Board, PieceType)>,
+    games: Vec<GameRecord>,
+    byes: Vec<(usize, usize)>,
+}
+
+impl Tournament {
+    /// `names`のプレイヤーのトーナメントを生成します。
+    /// `openings`の開始局面を回戦ごとに順番に使い、空の場合は初期配置から対局します。
+    /// 
+    pub fn new(format: Format, names: Vec<String>, x_size: usize, y_size: usize, openings: Vec<(Board, PieceType)>) -> Tournament {
+        let openings = if openings.is_empty() {
+            vec![(Board::new(x_size, y_size), PieceType::Black)]
+        } else {
+            openings
+        };
+
+        Tournament {
+            format,
+            names,
+            x_size,
+            y_size,
+            openings,
+            games: Vec::new(),
+            byes: Vec::new(),
+        }
+    }
+
+    /// 記録済みの対局を返します。
+    /// 
+    pub fn games(&self) -> &[GameRecord] {
+        &self.games
+    }
+
+    /// 全部で何回戦かを返します。
+    /// 
+    pub fn rounds(&self) -> usize {
+        let players = self.names.len();
+        match self.format {
+            _ if players < 2 => 0,
+            Format::RoundRobin { cycles } => cycles * (players + players % 2 - 1),
+            Format::Swiss { rounds } => rounds,
+        }
+    }
+
+    /// 全ての対局が終わっている場合にtrueを返します。
+    /// 
+    pub fn is_finished(&self) -> bool {
+        self.next_round() > self.rounds()
+    }
+
+    /// `round`回戦の組み合わせと不戦勝のプレイヤーを返します。
+    /// スイス式の組み合わせはそれより前の回戦の結果から決まります。
+    /// `round`が1から`rounds()`の範囲に無い場合は対局も不戦勝もありません。
+    /// 
+    pub fn pairings(&self, round: usize) -> (Vec<(usize, usize)>, Option<usize>) {
+        if round == 0 || round > self.rounds() {
+            return (Vec::new(), None);
+        }
+
+        match self.format {
+            Format::RoundRobin { .. } => self.round_robin_pairings(round),
+            Format::Swiss { .. } => self.swiss_pairings(round),
+        }
+    }
+
+    /// 残りの対局を全て行います。`state_path`を指定すると1局ごとに状態ファイルを書き出します。
+    /// `players`は`names`と同じ順番で渡します。
+    /// 
+    pub fn run(&mut self, players: &mut [Box<dyn Player>], state_path: Option<&Path>) -> io::Result<()> {
+        if players.len() != self.names.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "プレイヤーの数が参加者の数と違います"));
+        }
+
+        while !self.is_finished() {
+            let round = self.next_round();
+            let (pairings, bye) = self.pairings(round);
+            let (board, turn) = self.openings[(round - 1) % self.openings.len()].clone();
+
+            if let Some(player) = bye {
+                if !self.byes.contains(&(round, player)) {
+                    self.byes.push((round, player));
+                }
+            }
+            for (first, second) in pairings {
+                // 開始局面の手番を`first`、`second`の順に持たせる
+                for (mover, other) in [(first, second), (second, first)] {
+                    let (black, white) = if turn == PieceType::Black { (mover, other) } else { (other, mover) };
+                    if self.has_game(round, black, white) {
+                        continue;
+                    }
+
+                    let (black_player, white_player) = pair_mut(players, black, white);
+                    let disc_difference = arena::play_game(black_player, white_player, &board, turn);
+                    self.games.push(GameRecord { round, black, white, disc_difference });
+                    if let Some(path) = state_path {
+                        self.save(path)?;
+                    }
+                }
+            }
+        }
+
+        match state_path {
+            Some(path) => self.save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// 得点、ブッフホルツ、ゾンネボーン・ベルガー、石差の順に並べた順位表を返します。
+    /// 
+    pub fn standings(&self) -> Vec<Standing> {
+        let scores = (0..self.names.len()).map(|player| self.score(player)).collect::<Vec<_>>();
+
+        let mut standings = (0..self.names.len())
+            .map(|player| {
+                let mut standing = Standing {
+                    player,
+                    name: self.names[player].clone(),
+                    score: scores[player],
+                    wins: 0,
+                    draws: 0,
+                    losses: 0,
+                    buchholz: 0.0,
+                    sonneborn_berger: 0.0,
+                    disc_difference: 0,
+                };
+                for (opponent, points, disc_difference) in self.results(player) {
+                    match points.partial_cmp(&0.5) {
+                        Some(Ordering::Greater) => standing.wins += 1,
+                        Some(Ordering::Equal) => standing.draws += 1,
+                        _ => standing.losses += 1,
+                    }
+                    standing.sonneborn_berger += points * scores[opponent];
+                    standing.disc_difference += disc_difference as i64;
+                }
+                standing.buchholz = self.opponents(player).map(|opponent| scores[opponent]).sum();
+                standing
+            })
+            .collect::<Vec<_>>();
+
+        standings.sort_by(|a, b| {
+            b.score.total_cmp(&a.score)
+                .then(b.buchholz.total_cmp(&a.buchholz))
+                .then(b.sonneborn_berger.total_cmp(&a.sonneborn_berger))
+                .then(b.disc_difference.cmp(&a.disc_difference))
+                .then(a.player.cmp(&b.player))
+        });
+        standings
+    }
+
+    /// 順位表と対戦表をテキストで返します。対戦表の列は順位の番号で、値は相手から得た点数です。
+    /// 
+    pub fn crosstable(&self) -> String {
+        let standings = self.standings();
+        let width = standings.iter().map(|standing| standing.name.chars().count()).max().unwrap_or(0).max(4);
+
+        let mut text = format!("{:>2} {:<width$} {:>3} {:>4} {:>6} {:>6} {:>4} |", "#", "名前", "得点", "勝分敗", "BH", "SB", "石差", width = width - 2);
+        for rank in 1..=standings.len() {
+            text += &format!(" {:>4}", rank);
+        }
+        text.push('\n');
+
+        for (rank, standing) in standings.iter().enumerate() {
+            let record = format!("{}-{}-{}", standing.wins, standing.draws, standing.losses);
+            text += &format!(
+                "{:>2} {:<width$} {:>5.1} {:>7} {:>6.1} {:>6.2} {:>+6} |",
+                rank + 1, standing.name, standing.score, record, standing.buchholz, standing.sonneborn_berger, standing.disc_difference, width = width
+            );
+            for cell in self.crosstable_row(standing.player, &standings) {
+                text += &format!(" {:>4}", cell);
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// 順位表と対戦表をCSVで返します。
+    /// 
+    pub fn crosstable_csv(&self) -> String {
+        let standings = self.standings();
+
+        let mut csv = "rank,name,score,wins,draws,losses,buchholz,sonneborn_berger,disc_difference".to_string();
+        for rank in 1..=standings.len() {
+            csv += &format!(",{}", rank);
+        }
+        csv.push('\n');
+
+        for (rank, standing) in standings.iter().enumerate() {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{}",
+                rank + 1, escape_csv(&standing.name), standing.score, standing.wins, standing.draws, standing.losses,
+                standing.buchholz, standing.sonneborn_berger, standing.disc_difference
+            );
+            for cell in self.crosstable_row(standing.player, &standings) {
+                csv += &format!(",{}", cell);
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// 状態ファイルに書き出します。
+    /// 
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        // 書き出しの途中で止まっても元のファイルが壊れないよう、別のファイルに書いてから置き換える
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_string())?;
+        fs::rename(&temporary, path)
+    }
+
+    /// 状態ファイルから読み込みます。
+    /// 
+    pub fn load(path: &Path) -> io::Result<Tournament> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|error: ParseStateError| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))
+    }
+
+    // まだ全ての対局が終わっていない最初の回戦
+    fn next_round(&self) -> usize {
+        (1..=self.rounds())
+            .find(|&round| {
+                let (pairings, bye) = self.pairings(round);
+                let games = self.games.iter().filter(|game| game.round == round).count();
+                games < pairings.len() * GAMES_PER_PAIRING || bye.is_some_and(|player| !self.byes.contains(&(round, player)))
+            })
+            .unwrap_or(self.rounds() + 1)
+    }
+
+    fn has_game(&self, round: usize, black: usize, white: usize) -> bool {
+        self.games.iter().any(|game| game.round == round && game.black == black && game.white == white)
+    }
+
+    // 円形配置で総当たりの組み合わせを作る。奇数の場合は架空の参加者と当たったプレイヤーが休み(得点なし)
+    fn round_robin_pairings(&self, round: usize) -> (Vec<(usize, usize)>, Option<usize>) {
+        let players = self.names.len();
+        let size = players + players % 2;
+        let round_in_cycle = (round - 1) % (size - 1);
+
+        let mut circle = (1..size).collect::<Vec<_>>();
+        circle.rotate_right(round_in_cycle);
+        circle.insert(0, 0);
+
+        let pairings = (0..size / 2)
+            .map(|index| (circle[index], circle[size - 1 - index]))
+            .filter(|&(first, second)| first < players && second < players)
+            .collect();
+        (pairings, None)
+    }
+
+    fn swiss_pairings(&self, round: usize) -> (Vec<(usize, usize)>, Option<usize>) {
+        let previous = Tournament {
+            format: self.format,
+            names: self.names.clone(),
+            x_size: self.x_size,
+            y_size: self.y_size,
+            openings: Vec::new(),
+            games: self.games.iter().filter(|game| game.round < round).copied().collect(),
+            byes: self.byes.iter().filter(|&&(bye_round, _)| bye_round < round).copied().collect(),
+        };
+        let mut order = previous.standings().into_iter().map(|standing| standing.player).collect::<Vec<_>>();
+
+        let bye = if order.len() % 2 == 1 {
+            let index = order.iter().rposition(|player| !previous.byes.iter().any(|&(_, bye)| bye == *player)).unwrap_or(order.len() - 1);
+            Some(order.remove(index))
+        } else {
+            None
+        };
+
+        let pairings = pair_without_rematch(&previous, &order).unwrap_or_else(|| order.chunks(2).map(|pair| (pair[0], pair[1])).collect());
+        (pairings, bye)
+    }
+
+    fn score(&self, player: usize) -> f64 {
+        let byes = self.byes.iter().filter(|&&(_, bye)| bye == player).count() as f64;
+        self.results(player).map(|(_, points, _)| points).sum::<f64>() + byes * BYE_POINTS
+    }
+
+    // (相手, 得点, 石差)
+    fn results(&self, player: usize) -> impl Iterator<Item = (usize, f64, i32)> + '_ {
+        self.games.iter().filter_map(move |game| {
+            let (opponent, disc_difference) = if game.black == player {
+                (game.white, game.disc_difference)
+            } else if game.white == player {
+                (game.black, -game.disc_difference)
+            } else {
+                return None;
+            };
+            let points = match disc_difference.signum() {
+                1 => 1.0,
+                0 => 0.5,
+                _ => 0.0,
+            };
+            Some((opponent, points, disc_difference))
+        })
+    }
+
+    // 対戦した相手。1回の対戦(2局)を1人として数える
+    fn opponents(&self, player: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut pairings = self.games.iter()
+            .filter(move |game| game.black == player || game.white == player)
+            .map(move |game| (game.round, if game.black == player { game.white } else { game.black }))
+            .collect::<Vec<_>>();
+        pairings.dedup();
+        pairings.into_iter().map(|(_, opponent)| opponent)
+    }
+
+    fn has_met(&self, player: usize, opponent: usize) -> bool {
+        self.results(player).any(|(other, _, _)| other == opponent)
+    }
+
+    fn crosstable_row(&self, player: usize, standings: &[Standing]) -> Vec<String> {
+        standings.iter()
+            .map(|standing| {
+                if standing.player == player {
+                    "-".to_string()
+                } else if self.has_met(player, standing.player) {
+                    let points = self.results(player)
+                        .filter(|&(opponent, _, _)| opponent == standing.player)
+                        .map(|(_, points, _)| points)
+                        .sum::<f64>();
+                    format!("{:.1}", points)
+                } else {
+                    String::new()
+                }
+            })
+            .collect()
+    }
+}
+
+/// 状態ファイルの形式で書き出します。
+/// 
+/// `size`、`format`、`player`、`opening`の行に続けて、1局ごとに`game 回戦 黒 白 石差`、
+/// 不戦勝ごとに`bye 回戦 プレイヤー`の行を並べます。プレイヤーは参加順の番号(0始まり)です。
+/// 
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "size {} {}", self.x_size, self.y_size)?;
+        match self.format {
+            Format::RoundRobin { cycles } => writeln!(f, "format round_robin {}", cycles)?,
+            Format::Swiss { rounds } => writeln!(f, "format swiss {}", rounds)?,
+        }
+        for name in &self.names {
+            writeln!(f, "player {}", name)?;
+        }
+        for (board, turn) in &self.openings {
+            writeln!(f, "opening {}", board.to_position_str(*turn))?;
+        }
+        for game in &self.games {
+            writeln!(f, "game {} {} {} {:+}", game.round, game.black, game.white, game.disc_difference)?;
+        }
+        for (round, player) in &self.byes {
+            writeln!(f, "bye {} {}", round, player)?;
+        }
+        Ok(())
+    }
+}
+
+/// 状態ファイルから読み込みます。
+/// 
+impl std::str::FromStr for Tournament {
+    type Err = ParseStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut size = None;
+        let mut format = None;
+        let mut names = Vec::new();
+        let mut openings = Vec::new();
+        let mut games = Vec::new();
+        let mut byes = Vec::new();
+
+        for (index, line) in s.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim_end();
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (key, rest) = line.split_once(' ').ok_or(ParseStateError::InvalidLine(line_number))?;
+            let numbers = || -> Result<Vec<i64>, ParseStateError> {
+                rest.split_whitespace()
+                    .map(|value| value.parse::<i64>().map_err(|_| ParseStateError::InvalidLine(line_number)))
+                    .collect()
+            };
+            let player = |value: i64| -> Result<usize, ParseStateError> {
+                usize::try_from(value).ok().filter(|&player| player < names.len()).ok_or(ParseStateError::UnknownPlayer(line_number))
+            };
+
+            match key {
+                "size" => match numbers()?[..] {
+                    [x_size, y_size] if x_size > 0 && y_size > 0 => size = Some((x_size as usize, y_size as usize)),
+                    _ => return Err(ParseStateError::InvalidLine(line_number)),
+                },
+                "format" => {
+                    let (kind, count) = rest.split_once(' ').ok_or(ParseStateError::InvalidLine(line_number))?;
+                    let count = count.trim().parse::<usize>().map_err(|_| ParseStateError::InvalidLine(line_number))?;
+                    format = Some(match kind {
+                        "round_robin" => Format::RoundRobin { cycles: count },
+                        "swiss" => Format::Swiss { rounds: count },
+                        _ => return Err(ParseStateError::InvalidLine(line_number)),
+                    });
+                }
+                "player" => names.push(rest.to_string()),
+                "opening" => {
+                    let (x_size, y_size) = size.ok_or(ParseStateError::InvalidLine(line_number))?;
+                    let opening = Board::from_position_str(rest, x_size, y_size)
+                        .map_err(|error| ParseStateError::InvalidPosition(line_number, error))?;
+                    openings.push(opening);
+                }
+                "game" => match numbers()?[..] {
+                    [round, black, white, disc_difference] if round > 0 => games.push(GameRecord {
+                        round: round as usize,
+                        black: player(black)?,
+                        white: player(white)?,
+                        disc_difference: disc_difference as i32,
+                    }),
+                    _ => return Err(ParseStateError::InvalidLine(line_number)),
+                },
+                "bye" => match numbers()?[..] {
+                    [round, bye] if round > 0 => byes.push((round as usize, player(bye)?)),
+                    _ => return Err(ParseStateError::InvalidLine(line_number)),
+                },
+                _ => return Err(ParseStateError::InvalidLine(line_number)),
+            }
+        }
+
+        let (x_size, y_size) = size.ok_or(ParseStateError::MissingField("size"))?;
+        let format = format.ok_or(ParseStateError::MissingField("format"))?;
+        let mut tournament = Tournament::new(format, names, x_size, y_size, openings);
+        tournament.games = games;
+        tournament.byes = byes;
+        Ok(tournament)
+    }
+}
+
+/// 状態ファイルを読み込めなかった理由。行番号は1始まりです。
+/// 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseStateError {
+    /// 知らない行か、値が不正
+    InvalidLine(usize),
+    /// 開始局面が不正
+    InvalidPosition(usize, ParseBoardError),
+    /// 参加していないプレイヤーの番号
+    UnknownPlayer(usize),
+    /// 必要な行が無い
+    MissingField(&'static str),
+}
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseStateError::InvalidLine(line) => write!(f, "{}行目: 不正な行です", line),
+            ParseStateError::InvalidPosition(line, error) => write!(f, "{}行目: {}", line, error),
+            ParseStateError::UnknownPlayer(line) => write!(f, "{}行目: 参加していないプレイヤーです", line),
+            ParseStateError::MissingField(field) => write!(f, "{}の行がありません", field),
+        }
+    }
+}
+
+impl Error for ParseStateError {}
+
+// 上から順に、まだ対戦していない相手と組む。組めない場合は1つ前の組み合わせからやり直す
+fn pair_without_rematch(tournament: &Tournament, order: &[usize]) -> Option<Vec<(usize, usize)>> {
+    let (&first, rest) = match order.split_first() {
+        Some(split) => split,
+        None => return Some(Vec::new()),
+    };
+
+    for (index, &opponent) in rest.iter().enumerate() {
+        if tournament.has_met(first, opponent) {
+            continue;
+        }
+        let mut remaining = rest.to_vec();
+        remaining.remove(index);
+        if let Some(mut pairings) = pair_without_rematch(tournament, &remaining) {
+            pairings.insert(0, (first, opponent));
+            return Some(pairings);
+        }
+    }
+    None
+}
+
+fn pair_mut(players: &mut [Box<dyn Player>], first: usize, second: usize) -> (&mut dyn Player, &mut dyn Player) {
+    if first < second {
+        let (left, right) = players.split_at_mut(second);
+        (left[first].as_mut(), right[0].as_mut())
+    } else {
+        let (left, right) = players.split_at_mut(first);
+        (right[0].as_mut(), left[second].as_mut())
+    }
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greedy_player::GreedyPlayer;
+    use crate::random_player::RandomPlayer;
+    use std::collections::HashSet;
+
+    fn names(count: usize) -> Vec<String> {
+        (1..=count).map(|number| format!("P{}", number)).collect()
+    }
+
+    fn players() -> Vec<Box<dyn Player>> {
+        vec![
+            Box::new(GreedyPlayer::new()),
+            Box::new(RandomPlayer::new(1)),
+            Box::new(RandomPlayer::new(2)),
+            Box::new(RandomPlayer::new(3)),
+            Box::new(RandomPlayer::new(4)),
+        ]
+    }
+
+    #[test]
+    fn test_round_robin_pairings() {
+        for count in [4, 5] {
+            let tournament = Tournament::new(Format::RoundRobin { cycles: 1 }, names(count), 8, 8, Vec::new());
+            assert_eq!(if count == 4 { 3 } else { 5 }, tournament.rounds());
+
+            // 全ての組み合わせが1回ずつ現れる
+            let mut pairs = HashSet::new();
+            for round in 1..=tournament.rounds() {
+                let (pairings, bye) = tournament.pairings(round);
+                assert_eq!(None, bye);
+                for (first, second) in pairings {
+                    assert!(pairs.insert((first.min(second), first.max(second))));
+                }
+            }
+            assert_eq!(count * (count - 1) / 2, pairs.len());
+        }
+    }
+
+    #[test]
+    fn test_pairings_out_of_range() {
+        // 範囲外の回戦や参加者のいない大会は組み合わせが無い
+        let tournament = Tournament::new(Format::RoundRobin { cycles: 1 }, names(4), 8, 8, Vec::new());
+        assert_eq!((Vec::new(), None), tournament.pairings(0));
+        assert_eq!((Vec::new(), None), tournament.pairings(tournament.rounds() + 1));
+
+        for format in [Format::RoundRobin { cycles: 1 }, Format::Swiss { rounds: 3 }] {
+            let tournament = Tournament::new(format, Vec::new(), 8, 8, Vec::new());
+            assert_eq!((Vec::new(), None), tournament.pairings(1));
+            assert!(tournament.is_finished());
+        }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut tournament = Tournament::new(Format::RoundRobin { cycles: 1 }, names(3), 8, 8, arena::openings(8, 8, 2));
+        let mut players = players().into_iter().take(3).collect::<Vec<_>>();
+        tournament.run(&mut players, None).unwrap();
+
+        assert!(tournament.is_finished());
+        assert_eq!(6, tournament.games().len());
+        let standings = tournament.standings();
+        assert_eq!(6.0, standings.iter().map(|standing| standing.score).sum::<f64>());
+        assert!(standings.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        // 各プレイヤーの対戦相手は2人
+        assert!(standings.iter().all(|standing| standing.wins + standing.draws + standing.losses == 4));
+        let expected = standings.iter().map(|standing| {
+            standings.iter().filter(|other| other.player != standing.player).map(|other| other.score).sum::<f64>()
+        });
+        assert!(standings.iter().zip(expected).all(|(standing, buchholz)| standing.buchholz == buchholz));
+
+        let text = tournament.crosstable();
+        assert_eq!(4, text.lines().count());
+        assert!(text.lines().nth(1).unwrap().contains(&standings[0].name));
+        let csv = tournament.crosstable_csv();
+        assert_eq!(4, csv.lines().count());
+        assert!(csv.lines().nth(1).unwrap().starts_with(&format!("1,{},{}", standings[0].name, standings[0].score)));
+    }
+
+    #[test]
+    fn test_swiss() {
+        let mut tournament = Tournament::new(Format::Swiss { rounds: 3 }, names(5), 8, 8, arena::openings(8, 8, 3));
+        tournament.run(&mut players(), None).unwrap();
+
+        // 再戦は無く、不戦勝は毎回違うプレイヤー
+        let mut pairs = HashSet::new();
+        for round in 1..=3 {
+            let (pairings, _) = tournament.pairings(round);
+            assert_eq!(2, pairings.len());
+            for (first, second) in pairings {
+                assert!(pairs.insert((first.min(second), first.max(second))));
+            }
+        }
+        assert_eq!(3, tournament.byes.iter().map(|&(_, player)| player).collect::<HashSet<_>>().len());
+        assert_eq!(12, tournament.games().len());
+        assert_eq!(12.0 + 3.0 * BYE_POINTS, tournament.standings().iter().map(|standing| standing.score).sum::<f64>());
+    }
+
+    #[test]
+    fn test_resume() {
+        let path = std::env::temp_dir().join(format!("rust_reversi_tournament_{}.txt", std::process::id()));
+        let mut tournament = Tournament::new(Format::Swiss { rounds: 2 }, names(4), 8, 8, arena::openings(8, 8, 2));
+        tournament.run(&mut players().into_iter().take(4).collect::<Vec<_>>(), Some(&path)).unwrap();
+
+        let state = fs::read_to_string(&path).unwrap();
+        assert_eq!(state, tournament.to_string());
+        assert_eq!(state, Tournament::load(&path).unwrap().to_string());
+
+        // 途中までの状態から再開すると残りの対局だけを行う
+        let partial = state.lines().filter(|line| !line.starts_with("game 2")).collect::<Vec<_>>().join("\n");
+        fs::write(&path, partial).unwrap();
+        let mut resumed = Tournament::load(&path).unwrap();
+        assert!(!resumed.is_finished());
+        resumed.run(&mut players().into_iter().take(4).collect::<Vec<_>>(), Some(&path)).unwrap();
+        assert!(resumed.is_finished());
+        assert_eq!(tournament.games()[..4], resumed.games()[..4]);
+        assert_eq!(tournament.pairings(2), resumed.pairings(2));
+        assert_eq!(8, resumed.games().len());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(Err(ParseStateError::MissingField("format")), "size 8 8".parse::<Tournament>().map(|_| ()));
+        assert_eq!(Err(ParseStateError::UnknownPlayer(3)), "size 8 8\nplayer a\ngame 1 0 1 +2".parse::<Tournament>().map(|_| ()));
+    }
+}
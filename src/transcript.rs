@@ -0,0 +1,151 @@
+//! "f5d6c3d3c4..."のように座標を連結した棋譜の読み書き
+//! 
+//! パスは棋譜に含めず、打てる場所が無い手番を自動でパスとして扱います。
+//! 
+
+use crate::game::Game;
+use crate::move_error::MoveError;
+use crate::move_type::MoveType;
+use crate::point::{ParsePointError, Point};
+use std::error::Error;
+use std::fmt;
+
+/// 棋譜を座標の列に分解します。座標の間の空白は無視します。
+/// 
+pub fn parse(transcript: &str) -> Result<Vec<Point>, TranscriptError> {
+    let mut points = Vec::new();
+    let mut chars = transcript.chars().filter(|c| !c.is_whitespace()).peekable();
+
+    while chars.peek().is_some() {
+        // 列の英字と行の数字を一手分として切り出す
+        let mut token = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+            token.push(c);
+        }
+        while let Some(c) = chars.next_if(|c| !c.is_ascii_alphabetic()) {
+            token.push(c);
+        }
+
+        let point = token.parse::<Point>().map_err(|error| TranscriptError::Parse {
+            ply: points.len() + 1,
+            error,
+        })?;
+        points.push(point);
+    }
+
+    Ok(points)
+}
+
+/// 棋譜を`x_size`x`y_size`の盤面で最初から再生します。
+/// 打てる場所が無い手番は自動でパスし、打てない手が含まれていた場合は何手目かと理由を返却します。
+/// 
+pub fn replay(transcript: &str, x_size: usize, y_size: usize) -> Result<Game, TranscriptError> {
+    let mut game = Game::new(x_size, y_size);
+
+    for (index, point) in parse(transcript)?.into_iter().enumerate() {
+        pass_if_needed(&mut game);
+        game.play(point).map_err(|error| TranscriptError::IllegalMove {
+            ply: index + 1,
+            error,
+        })?;
+    }
+    pass_if_needed(&mut game);
+
+    Ok(game)
+}
+
+/// ゲームの手を棋譜の文字列にします。パスは含まれません。
+/// 
+pub fn write(game: &Game) -> String {
+    game.moves()
+        .iter()
+        .filter_map(|move_type| match move_type {
+            MoveType::Put(point) => point.to_notation(),
+            MoveType::Pass => None,
+        })
+        .collect()
+}
+
+fn pass_if_needed(game: &mut Game) {
+    if game.must_pass() {
+        // must_passがtrueならパスは必ず成功する
+        let _ = game.pass();
+    }
+}
+
+/// 棋譜を読み込めなかった理由
+/// 
+/// `ply`はパスを除いた何手目か(1始まり)を表します。
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TranscriptError {
+    /// 座標として読み取れない
+    Parse { ply: usize, error: ParsePointError },
+    /// 打てない手
+    IllegalMove { ply: usize, error: MoveError },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::Parse { ply, error } => write!(f, "{}手目: {}", ply, error),
+            TranscriptError::IllegalMove { ply, error } => write!(f, "{}手目: {}", ply, error),
+        }
+    }
+}
+
+impl Error for TranscriptError {}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece_type::PieceType;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Ok(vec![Point::new(6, 5), Point::new(4, 6), Point::new(3, 3)]),
+            parse("f5 D6c3")
+        );
+        assert_eq!(Ok(vec![Point::new(1, 10), Point::new(2, 3)]), parse("a10b3"));
+        assert_eq!(Ok(Vec::new()), parse(""));
+        assert_eq!(
+            Err(TranscriptError::Parse { ply: 2, error: ParsePointError::MissingRow }),
+            parse("f5d")
+        );
+    }
+
+    #[test]
+    fn test_replay_and_write() {
+        let transcript = "f5d6c3d3c4f4f6f3e6e7";
+        let game = replay(transcript, 8, 8).ok().unwrap();
+
+        assert_eq!(10, game.moves().len());
+        assert_eq!(PieceType::Black, game.turn());
+        assert_eq!(transcript, write(&game));
+    }
+
+    #[test]
+    fn test_replay_with_pass() {
+        // 4手目の後に黒がパスする
+        let game = replay("b1c1d3a1", 4, 4).ok().unwrap();
+
+        assert_eq!(MoveType::Pass, game.moves()[4]);
+        assert_eq!(PieceType::White, game.turn());
+        assert_eq!("b1c1d3a1", write(&game));
+    }
+
+    #[test]
+    fn test_replay_illegal() {
+        assert_eq!(
+            Some(TranscriptError::IllegalMove { ply: 2, error: MoveError::Occupied(Point::new(6, 5)) }),
+            replay("f5f5", 8, 8).err()
+        );
+        assert_eq!(
+            Some(TranscriptError::IllegalMove { ply: 1, error: MoveError::OutOfBounds(Point::new(9, 1)) }),
+            replay("i1", 8, 8).err()
+        );
+    }
+}
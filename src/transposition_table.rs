@@ -0,0 +1,200 @@
+use crate::point::Point;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// 保存できる深さと座標の上限
+const MAX_DEPTH: usize = 0xff;
+const COORDINATE_MASK: u64 = 0x7ff;
+
+/// 置換表に保存した評価値の種類
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    /// 正確な値
+    Exact,
+    /// 下限(実際の値はこれ以上)
+    Lower,
+    /// 上限(実際の値はこれ以下)
+    Upper,
+}
+
+/// # Entry 構造体
+/// 
+/// 置換表の1件分
+/// 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Entry {
+    /// 盤面と手番のハッシュ値
+    pub key: u64,
+    /// 探索した深さ
+    pub depth: usize,
+    /// 評価値
+    pub score: i32,
+    /// 評価値の種類
+    pub bound: Bound,
+    /// 最善手
+    pub best_move: Option<Point>,
+}
+
+/// # TranspositionTable 構造体
+/// 
+/// 探索済みの局面を保存する固定サイズの置換表
+/// 
+/// ハッシュ値の下位ビットで保存場所を決め、同じ場所には深く探索した方を残します。
+/// 
+/// 各件をハッシュ値と内容の2つの`u64`に詰めて保存するため、複数のスレッドからロック無しで共有できます。
+/// ハッシュ値には内容との排他的論理和を保存し、別のスレッドの書き込みと混ざった件は読み出しで捨てます。
+/// 深さは255、最善手の座標は2047までを保存します。
+/// 
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    mask: usize,
+}
+
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl TranspositionTable {
+    /// 件数を指定して置換表を生成します。件数は2の累乗に切り上げます。
+    /// 
+    pub fn new(size: usize) -> TranspositionTable {
+        let size = size.max(1).next_power_of_two();
+        TranspositionTable {
+            slots: (0..size).map(|_| Slot { key: AtomicU64::new(0), data: AtomicU64::new(0) }).collect(),
+            mask: size - 1,
+        }
+    }
+
+    /// 保存できる件数を返します。
+    /// 
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// ハッシュ値に一致する保存済みの局面を返します。
+    /// 
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = &self.slots[key as usize & self.mask];
+        let data = slot.data.load(Ordering::Relaxed);
+        if data == 0 || slot.key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        Some(decode(key, data))
+    }
+
+    /// 局面を保存します。同じ場所に別の局面があれば置き換え、同じ局面は深く探索した方を残します。
+    /// 
+    pub fn store(&self, entry: Entry) {
+        if let Some(old) = self.probe(entry.key) {
+            if old.depth > entry.depth {
+                return;
+            }
+        }
+
+        let slot = &self.slots[entry.key as usize & self.mask];
+        let data = encode(&entry);
+        slot.key.store(entry.key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// 保存した局面を全て消します。
+    /// 
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            *slot.key.get_mut() = 0;
+            *slot.data.get_mut() = 0;
+        }
+    }
+}
+
+// 評価値32ビット、深さ8ビット、種類2ビット、座標11ビットずつに詰める。種類は1始まりにして空の件と区別する
+fn encode(entry: &Entry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let (x, y) = entry.best_move.map_or((0, 0), |point| (point.x as u64, point.y as u64));
+
+    entry.score as u32 as u64
+        | (entry.depth.min(MAX_DEPTH) as u64) << 32
+        | bound << 40
+        | (x & COORDINATE_MASK) << 42
+        | (y & COORDINATE_MASK) << 53
+}
+
+fn decode(key: u64, data: u64) -> Entry {
+    let bound = match (data >> 40) & 0b11 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    let (x, y) = ((data >> 42) & COORDINATE_MASK, (data >> 53) & COORDINATE_MASK);
+
+    Entry {
+        key,
+        depth: ((data >> 32) & 0xff) as usize,
+        score: data as u32 as i32,
+        bound,
+        best_move: if x == 0 { None } else { Some(Point::new(x as usize, y as usize)) },
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, depth: usize) -> Entry {
+        Entry {
+            key,
+            depth,
+            score: 10,
+            bound: Bound::Exact,
+            best_move: Some(Point::new(4, 3)),
+        }
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let table = TranspositionTable::new(1000);
+        assert_eq!(1024, table.size());
+
+        table.store(entry(5, 3));
+        assert_eq!(Some(entry(5, 3)), table.probe(5));
+        assert_eq!(None, table.probe(5 + 1024));
+
+        // 同じ局面は深い方を残す
+        table.store(entry(5, 2));
+        assert_eq!(Some(entry(5, 3)), table.probe(5));
+
+        // 別の局面は置き換える
+        table.store(entry(5 + 1024, 1));
+        assert_eq!(None, table.probe(5));
+        assert_eq!(Some(entry(5 + 1024, 1)), table.probe(5 + 1024));
+
+        let mut table = table;
+        table.clear();
+        assert_eq!(None, table.probe(5 + 1024));
+    }
+
+    #[test]
+    fn test_encode() {
+        let table = TranspositionTable::new(16);
+        let entry = Entry {
+            key: 0x1234_5678_9abc_def0,
+            depth: 12,
+            score: -1_000_064,
+            bound: Bound::Upper,
+            best_move: None,
+        };
+        table.store(entry);
+        assert_eq!(Some(entry), table.probe(entry.key));
+
+        let entry = Entry { bound: Bound::Lower, best_move: Some(Point::new(2047, 1)), ..entry };
+        table.store(entry);
+        assert_eq!(Some(entry), table.probe(entry.key));
+    }
+}
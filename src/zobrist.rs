@@ -0,0 +1,37 @@
+//! Zobristハッシュ
+//! 
+//! 盤面の各マスと駒の組み合わせに乱数を割り当て、置かれている駒の乱数の排他的論理和を盤面のハッシュ値とします。
+//! 乱数は座標から計算するため、どの大きさの盤面でも表を用意せずに使えます。
+//! 
+
+use crate::piece_type::PieceType;
+use crate::point::Point;
+
+const SEED: u64 = 0x5265_7665_7273_6921;
+
+/// `point`の位置にある`piece_type`の駒の乱数を返します。
+/// 
+pub fn piece_key(point: &Point, piece_type: PieceType) -> u64 {
+    let color = match piece_type {
+        PieceType::Black => 0,
+        PieceType::White => 1,
+    };
+    mix(SEED ^ ((point.y as u64) << 33 | (point.x as u64) << 1 | color))
+}
+
+/// 手番の乱数を返します。黒番は0です。
+/// 
+pub fn side_key(piece_type: PieceType) -> u64 {
+    match piece_type {
+        PieceType::Black => 0,
+        PieceType::White => mix(!SEED),
+    }
+}
+
+// splitmix64の出力関数
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}